@@ -23,7 +23,17 @@
             cargoLock = {
               lockFile = ./Cargo.lock;
             };
-            cargoHash = "sha256-dYOdyZn0byioLz8GiT3aghjTUTBeQ3tdAk3UAlfe7d8=";
+            # stale since schemars/sha2/toml_edit were added; the first
+            # `nix build` prints the real hash to put here
+            cargoHash = pkgs.lib.fakeHash;
+
+            # JSON Schemas for the policy file and the status report
+            postInstall = ''
+              mkdir -p $out/share/sentinel/schemas
+              $out/bin/sentinelctl schema policy > $out/share/sentinel/schemas/policy.schema.json
+              $out/bin/sentinelctl schema policy --schema-version 1 > $out/share/sentinel/schemas/policy-v1.schema.json
+              $out/bin/sentinelctl schema status > $out/share/sentinel/schemas/status.schema.json
+            '';
 
             meta = with pkgs.lib; {
               description = "Sentinel CLI (policy validation + status reporting)";
@@ -2,26 +2,6 @@
 let
   cfg = config.services.sentinel;
 
-  writer = pkgs.writeShellScriptBin "sentinel-write-status" ''
-    set -euo pipefail
-    umask 022
-
-    mkdir -p "$(dirname "${cfg.statusPath}")"
-
-    tmp="$(mktemp --tmpdir="$(dirname "${cfg.statusPath}")" sentinel.status.XXXXXX)"
-    trap 'rm -f "$tmp"' EXIT
-
-    ${lib.getExe cfg.package} check --json \
-      --backend ${cfg.backend} \
-      --policy ${cfg.policyFile} \
-      ${lib.escapeShellArgs cfg.extraArgs} \
-      > "$tmp"
-
-    chmod 0644 "$tmp"
-    mv -f "$tmp" "${cfg.statusPath}"
-    trap - EXIT
-  '';
-
   waybarDir = cfg.waybar.assetsDir;
 
   waybarScript = pkgs.writeShellScript "sentinel-waybar.sh" ''
@@ -43,8 +23,8 @@ let
 
     tooltip="$("$JQ" -r '
       "Sentinel (" + (.backend // "unknown") + "): " + (.overall // "unknown") + "\n" +
-      "warn=" + ((.summary.checks_warn // 0)|tostring) + " failed=" + ((.summary.checks_failed // 0)|tostring) + "\n\n" +
-      ((.findings // []) | map(.severity + ": " + .id + " — " + .msg) | .[0:10] | join("\n"))
+      "passed=" + ((.summary.checks_passed // 0)|tostring) + " warn=" + ((.summary.checks_warn // 0)|tostring) + " failed=" + ((.summary.checks_failed // 0)|tostring) + "\n\n" +
+      ((.findings // []) | map(select(.severity != "ok") | .severity + ": " + .id + " — " + .msg) | .[0:10] | join("\n"))
     ' "$STATUS_FILE")"
 
     "$JQ" -n --arg text "$text" --arg class "$overall" --arg tooltip "$tooltip" \
@@ -97,11 +77,11 @@ in
 
     policyFile = lib.mkOption {
       type = lib.types.path;
-      description = "Path to sentinel policy TOML (schema = 1).";
+      description = "Path to sentinel policy TOML (schema 1 or 2).";
     };
 
     backend = lib.mkOption {
-      type = lib.types.enum [ "auto" "firewalld" "nftables" ];
+      type = lib.types.enum [ "auto" "firewalld" "ufw" "nftables" "iptables" ];
       default = "auto";
       description = "Backend selection for sentinelctl.";
     };
@@ -109,7 +89,7 @@ in
     interval = lib.mkOption {
       type = lib.types.str;
       default = "30s";
-      description = "systemd timer interval (e.g. 10s, 30s, 2min).";
+      description = "Re-check interval for `sentinelctl watch` (e.g. 500ms, 30s, 2min); changes reported by nft monitor / firewalld D-Bus signals trigger an immediate re-check.";
     };
 
     statusPath = lib.mkOption {
@@ -121,9 +101,11 @@ in
     extraArgs = lib.mkOption {
       type = lib.types.listOf lib.types.str;
       default = [ ];
-      description = "Extra args passed to sentinelctl check.";
+      description = "Extra args passed to sentinelctl watch.";
     };
 
+    networkManagerDispatch = lib.mkEnableOption "a NetworkManager dispatcher script that re-checks (and re-selects the policy profile) when connectivity changes";
+
     waybar = {
       enable = lib.mkEnableOption "Install Waybar helper assets (placeholder for future assets)";
 
@@ -164,12 +146,40 @@ in
       [ pkgs.jq pkgs.fuzzel pkgs.less ] ++ [ cfg.package ]
     );
 
-    systemd.services.sentinel-check = {
-      description = "Sentinel policy check (writes status JSON)";
+    networking.networkmanager.dispatcherScripts = lib.mkIf cfg.networkManagerDispatch [
+      {
+        type = "basic";
+        source = pkgs.writeShellScript "90-sentinel" ''
+          export XDG_STATE_HOME=/var/lib/sentinel
+          exec ${lib.escapeShellArgs [
+            (lib.getExe cfg.package)
+            "--backend" cfg.backend
+            "--policy" "${cfg.policyFile}"
+            "nm-dispatch"
+            "--output" cfg.statusPath
+          ]} "$1" "$2"
+        '';
+      }
+    ];
+
+    systemd.services.sentinel-watch = {
+      description = "Sentinel policy watcher (writes status JSON)";
       wantedBy = [ "multi-user.target" ];
+      after = [ "firewalld.service" "nftables.service" "iptables.service" "ufw.service" ];
+      # baseline + check history live under $XDG_STATE_HOME/sentinel
+      environment.XDG_STATE_HOME = "/var/lib/sentinel";
       serviceConfig = {
-        Type = "oneshot";
-        ExecStart = "${writer}/bin/sentinel-write-status";
+        Type = "simple";
+        ExecStart = lib.escapeShellArgs ([
+          (lib.getExe cfg.package)
+          "--backend" cfg.backend
+          "--policy" "${cfg.policyFile}"
+          "watch"
+          "--interval" cfg.interval
+          "--output" cfg.statusPath
+        ] ++ cfg.extraArgs);
+        Restart = "on-failure";
+        RestartSec = "5s";
 
         NoNewPrivileges = true;
         PrivateTmp = true;
@@ -185,21 +195,11 @@ in
 
         RuntimeDirectory = "sentinel";
         RuntimeDirectoryMode = "0755";
+        StateDirectory = "sentinel";
+        StateDirectoryMode = "0700";
 
         SyslogIdentifier = "sentinel";
       };
     };
-
-    systemd.timers.sentinel-check = {
-      description = "Run Sentinel policy checks periodically";
-      wantedBy = [ "timers.target" ];
-      timerConfig = {
-        OnBootSec = "30s";
-        OnUnitActiveSec = cfg.interval;
-        AccuracySec = "1s";
-        RandomizedDelaySec = "2s";
-        Unit = "sentinel-check.service";
-      };
-    };
   };
 }
@@ -1,24 +1,60 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use chrono::Utc;
+use std::net::SocketAddr;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::{
+    net::TcpListener,
+    signal::unix::{signal, SignalKind},
+    time,
+};
+use tracing::{debug, info, warn};
 
 use crate::{
-    backends::{all_backends, firewalld::FirewalldBackend, nftables::NftablesBackend, Backend},
-    cli::{BackendChoice, BackendCmd, Cli, Command},
-    config::{default_policy_path, load_policy, Policy},
-    status::{ExitStatus, Overall, StatusReport, Summary},
+    backends::{
+        self, builtin_backends, file::FileBackend, firewalld::FirewalldBackend,
+        iptables::IptablesBackend, nftables::NftablesBackend, plugin, ufw::UfwBackend, Backend,
+        BackendStatus,
+    },
+    baseline,
+    cli::{
+        BackendChoice, BackendCmd, BaselineCmd, CheckFormat, Cli, Command, OutputArgs, PolicyCmd,
+        SchemaKind, SnapshotCmd,
+    },
+    cmd::{self, spawn_lines, LineStream},
+    config::{default_policy_path, load_policy, Policy, DEFAULT_PROFILE},
+    diff::{self, DiffReport},
+    export, history, metrics, network, policy, schema,
+    status::{CheckStatus, ExitStatus, Finding, Overall, StatusReport, Summary, REPORT_SCHEMA},
+    statusfile,
 };
 
 pub async fn run(cli: Cli) -> Result<ExitStatus> {
+    if let Command::NmDispatch { iface, action, .. } = &cli.cmd {
+        if !action.triggers_check() {
+            debug!(iface, ?action, "ignoring NetworkManager dispatcher event");
+            return Ok(ExitStatus::Ok);
+        }
+    }
+
     match cli.cmd {
         Command::Backend { cmd } => {
             match cmd {
                 BackendCmd::List => {
-                    for b in all_backends() {
+                    let builtin = builtin_backends();
+                    for b in &builtin {
                         println!("{}", b.name());
                     }
+                    for p in plugin::discover() {
+                        if !builtin.iter().any(|b| b.name() == p.name()) {
+                            println!("{} (plugin: {})", p.name(), p.path().display());
+                        }
+                    }
                 }
                 BackendCmd::Detect => {
-                    for b in all_backends() {
+                    for b in builtin_backends() {
                         let ok = b.detect().await.unwrap_or(false);
                         println!("{}: {}", b.name(), if ok { "yes" } else { "no" });
                     }
@@ -26,74 +62,634 @@ pub async fn run(cli: Cli) -> Result<ExitStatus> {
             }
             Ok(ExitStatus::Ok)
         }
+        Command::History { json, limit } => {
+            let entries = history::load()?;
+            let transitions = history::transitions(&entries);
+            let recent = &transitions[transitions.len().saturating_sub(limit)..];
+            if json {
+                println!("{}", serde_json::to_string_pretty(recent)?);
+            } else if recent.is_empty() {
+                println!("no history recorded yet");
+            } else {
+                for t in recent {
+                    println!(
+                        "{} {}: {} -> {} (warn={} failed={})",
+                        t.ts.to_rfc3339(),
+                        t.backend,
+                        t.from.map(|o| o.as_str()).unwrap_or("none"),
+                        t.to.as_str(),
+                        t.summary.checks_warn,
+                        t.summary.checks_failed
+                    );
+                }
+            }
+            Ok(ExitStatus::Ok)
+        }
+        Command::Policy { cmd } => {
+            let policy_path = cli.policy.clone().unwrap_or(default_policy_path()?);
+            match cmd {
+                PolicyCmd::Show { resolved: true } => {
+                    print!(
+                        "{}",
+                        policy::render_resolved(&policy::resolve(&policy_path)?)
+                    );
+                }
+                PolicyCmd::Show { resolved: false } => {
+                    let s = std::fs::read_to_string(&policy_path).with_context(|| {
+                        format!("failed to read policy file: {}", policy_path.display())
+                    })?;
+                    print!("{s}");
+                }
+                PolicyCmd::Lint => {
+                    let problems = policy::lint::lint(&policy_path);
+                    for p in &problems {
+                        println!("{p}");
+                    }
+                    if !problems.is_empty() {
+                        eprintln!("{} problem(s) found", problems.len());
+                        return Ok(ExitStatus::Error);
+                    }
+                    println!("{}: ok", policy_path.display());
+                }
+                PolicyCmd::Migrate { write } => migrate_policy(&policy_path, write)?,
+            }
+            Ok(ExitStatus::Ok)
+        }
+        Command::Schema {
+            kind,
+            schema_version,
+        } => {
+            let value = match kind {
+                SchemaKind::Policy => schema::policy(schema_version.unwrap_or(policy::v2::LATEST))?,
+                SchemaKind::Status => {
+                    anyhow::ensure!(
+                        schema_version.is_none(),
+                        "--schema-version only applies to the policy schema"
+                    );
+                    schema::status()
+                }
+            };
+            println!("{}", serde_json::to_string_pretty(&value)?);
+            Ok(ExitStatus::Ok)
+        }
+        Command::Snapshot {
+            cmd: SnapshotCmd::Export { dir },
+        } => {
+            let policy_path = cli.policy.clone().unwrap_or(default_policy_path()?);
+            let policy_backend = load_policy(&policy_path).ok().and_then(|p| p.backend);
+            let backend = select_backend(&cli.backend, policy_backend.as_deref(), None).await?;
+            let recorded = backends::file::export(backend.as_ref(), &dir).await?;
+            println!(
+                "recorded {recorded}/{} {} command outputs in {}",
+                backend.snapshot_commands().len(),
+                backend.name(),
+                dir.display()
+            );
+            Ok(ExitStatus::Ok)
+        }
         _ => handle_status_like(cli).await,
     }
 }
 
 async fn handle_status_like(cli: Cli) -> Result<ExitStatus> {
     let policy_path = cli.policy.clone().unwrap_or(default_policy_path()?);
+
+    // long-running commands load the policy and select the backends on every
+    // pass, so a policy that is broken at startup is reported like a later one
+    match &cli.cmd {
+        Command::Watch {
+            interval,
+            out,
+            no_monitor,
+        } => return watch(&cli, &policy_path, *interval, out, !no_monitor).await,
+        Command::Serve {
+            metrics_listen,
+            interval,
+        } => return serve(&cli, &policy_path, *interval, *metrics_listen).await,
+        _ => {}
+    }
+
     let policy_res = load_policy(&policy_path);
 
     let backend_hint = backend_label(
-        cli.backend,
+        &cli.backend,
         policy_res.as_ref().ok().and_then(|p| p.backend.as_deref()),
     );
 
     let policy = match policy_res {
         Ok(p) => p,
-        Err(_) => {
-            let report = error_report(
-                &backend_hint,
-                "policy-load-failed",
-                "policy file missing or invalid (schema=1 required)",
-            );
-            return emit_report(cli.cmd, report);
+        Err(err) => {
+            let report = error_report(&backend_hint, "policy-load-failed", &format!("{err:#}"));
+            return emit_report(cli.cmd, report).await;
         }
     };
 
-    let backend = match select_backend(cli.backend, policy.backend.as_deref()).await {
+    let (policy, profile) = match resolve_profile(&policy, cli.profile.as_deref()).await {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            let report = error_report(&backend_hint, "profile-invalid", &format!("{err:#}"));
+            return emit_report(cli.cmd, report).await;
+        }
+    };
+
+    let backends = match select_backends(&cli.backend, &policy, cli.snapshot_from.as_deref()).await
+    {
         Ok(b) => b,
-        Err(_) => {
+        Err(err) => {
             let report = error_report(
-                &backend_label(cli.backend, policy.backend.as_deref()),
+                &backend_label(&cli.backend, policy.backend.as_deref()),
                 "backend-detect-failed",
-                "no supported firewall backend detected (firewalld or nftables)",
+                &format!("{err:#}"),
             );
-            return emit_report(cli.cmd, report);
+            return emit_report(cli.cmd, report).await;
         }
     };
 
     match cli.cmd {
-        Command::Status { json, one_line } => {
-            let report = build_report(backend.as_ref(), &policy).await?;
+        Command::Status {
+            json,
+            one_line,
+            out,
+        } => {
+            let report = build_report(&backends, &policy, &profile).await?;
+            save_report(&out, &report)?;
             output_report(&report, json, one_line);
             Ok(report.overall.into())
         }
-        Command::Check { json } => {
-            let report = build_report(backend.as_ref(), &policy).await?;
+        Command::Check { json, format, out } => {
+            let report = build_report(&backends, &policy, &profile).await?;
+            save_report(&out, &report)?;
+            print_check(&report, json, format)?;
+            Ok(report.overall.into())
+        }
+        Command::Diff {
+            json,
+            baseline,
+            no_color,
+        } => {
+            anyhow::ensure!(
+                baseline.is_none() || backends.len() == 1,
+                "--baseline needs a single backend (use --backend)"
+            );
+            let mut findings = Vec::new();
+            let mut baselines = Vec::new();
+            for backend in &backends {
+                let snap = backend.snapshot().await?;
+                findings.extend(tagged(backend.validate(&policy, &snap).await?, &snap));
+
+                let baseline_path = match &baseline {
+                    Some(p) => Some(p.clone()),
+                    None => baseline::rules_path(&snap.backend_name)
+                        .ok()
+                        .filter(|p| p.exists()),
+                };
+                baselines.push(match baseline_path {
+                    Some(path) => {
+                        let saved = std::fs::read_to_string(&path).with_context(|| {
+                            format!("failed to read baseline: {}", path.display())
+                        })?;
+                        diff::baseline_diff(
+                            &snap.backend_name,
+                            &path,
+                            &saved,
+                            &backend.normalize(&snap),
+                        )
+                    }
+                    None => diff::no_baseline(&snap.backend_name),
+                });
+            }
+            apply_severity(&policy, &mut findings);
+
+            let report = DiffReport {
+                backend: backends_label(&backends),
+                policy: diff::policy_diff(&findings),
+                baselines,
+            };
             if json {
                 println!("{}", serde_json::to_string_pretty(&report)?);
             } else {
-                println!("{}", report.overall.as_str());
+                diff::print_human(&report, diff::use_color(no_color));
             }
-            Ok(report.overall.into())
+            Ok(if report.has_changes() {
+                ExitStatus::Warn
+            } else {
+                ExitStatus::Ok
+            })
+        }
+        Command::NmDispatch {
+            iface,
+            action,
+            out,
+            exit_status,
+        } => {
+            let report = build_report(&backends, &policy, &profile).await?;
+            save_report(&out, &report)?;
+            info!(
+                iface,
+                ?action,
+                profile,
+                overall = report.overall.as_str(),
+                "re-checked after network change"
+            );
+            output_report(&report, false, true);
+            Ok(dispatch_exit(&report, exit_status))
         }
-        Command::Diff => {
-            let snap = backend.snapshot().await?;
-            println!("{}", snap.raw.trim());
+        Command::Metrics { textfile } => {
+            let report = build_report(&backends, &policy, &profile).await?;
+            write_metrics(&report, textfile.as_deref()).await?;
             Ok(ExitStatus::Ok)
         }
-        Command::Backend { .. } => unreachable!("handled earlier"),
+        Command::Baseline { cmd } => match cmd {
+            BaselineCmd::Save => {
+                let mut snaps = Vec::new();
+                for backend in &backends {
+                    let snap = backend.snapshot().await?;
+                    anyhow::ensure!(
+                        snap.active,
+                        "{} is not active; refusing to save a baseline",
+                        snap.backend_name
+                    );
+                    snaps.push(snap);
+                }
+                for (backend, snap) in backends.iter().zip(&snaps) {
+                    let saved = baseline::save(&snap.backend_name, &backend.normalize(snap))?;
+                    println!(
+                        "saved baseline: {} (sha256 {})",
+                        saved.path.display(),
+                        saved.digest
+                    );
+                }
+                Ok(ExitStatus::Ok)
+            }
+            BaselineCmd::Verify { json } => {
+                let mut findings = Vec::new();
+                for backend in &backends {
+                    let snap = backend.snapshot().await?;
+                    let verified = baseline::verify(&snap.backend_name, &backend.normalize(&snap));
+                    findings.extend(tagged(verified, &snap));
+                }
+                apply_severity(&policy, &mut findings);
+                let report = summarize(&backends_label(&backends), findings);
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                } else {
+                    print_human_readable(&report);
+                }
+                Ok(report.overall.into())
+            }
+        },
+        Command::Backend { .. }
+        | Command::History { .. }
+        | Command::Policy { .. }
+        | Command::Schema { .. }
+        | Command::Snapshot { .. }
+        | Command::Watch { .. }
+        | Command::Serve { .. } => {
+            unreachable!("handled earlier")
+        }
+    }
+}
+
+/// Re-evaluate until SIGINT/SIGTERM: every `interval`, and right after the
+/// first selected backend that has a change monitor reports a change. The
+/// policy is re-read, and the profile and backends re-selected, on every pass.
+async fn watch(
+    cli: &Cli,
+    policy_path: &Path,
+    interval: Duration,
+    out: &OutputArgs,
+    monitor: bool,
+) -> Result<ExitStatus> {
+    let mut changes = ChangeMonitor::default();
+    let mut sigterm =
+        signal(SignalKind::terminate()).context("failed to install SIGTERM handler")?;
+    let mut last = None;
+
+    loop {
+        let (report, backends) = check_pass(cli, policy_path).await;
+
+        // follow the change monitor of the backends selected this pass; keep
+        // the current one when the pass failed before selecting any
+        if !backends.is_empty() {
+            let wanted = backends
+                .iter()
+                .find_map(|b| b.monitor_command())
+                .filter(|_| monitor);
+            changes.follow(wanted);
+        }
+
+        if let Err(err) = save_report(out, &report) {
+            warn!(error = ?err, "failed to write status file");
+        }
+        if last != Some(report.overall) {
+            info!(overall = report.overall.as_str(), "status changed");
+            println!("sentinel:{}:{}", report.backend, report.overall.as_str());
+            last = Some(report.overall);
+        }
+
+        tokio::select! {
+            _ = time::sleep(interval) => {}
+            () = changes.changed() => {}
+            _ = tokio::signal::ctrl_c() => break,
+            _ = sigterm.recv() => break,
+        }
+    }
+
+    Ok(ExitStatus::Ok)
+}
+
+/// One re-check for `watch`/`serve`: re-reads the policy, re-selects the
+/// profile and backends, and returns the report with the backends it used.
+/// Failures become error reports instead of ending the loop.
+async fn check_pass(cli: &Cli, policy_path: &Path) -> (StatusReport, Vec<Box<dyn Backend>>) {
+    let policy = match load_policy(policy_path) {
+        Ok(policy) => policy,
+        Err(err) => {
+            let label = backend_label(&cli.backend, None);
+            let report = error_report(&label, "policy-load-failed", &format!("{err:#}"));
+            return (report, Vec::new());
+        }
+    };
+    let label = backend_label(&cli.backend, policy.backend.as_deref());
+    let (policy, profile) = match resolve_profile(&policy, cli.profile.as_deref()).await {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            let report = error_report(&label, "profile-invalid", &format!("{err:#}"));
+            return (report, Vec::new());
+        }
+    };
+    let backends = match select_backends(&cli.backend, &policy, cli.snapshot_from.as_deref()).await
+    {
+        Ok(backends) => backends,
+        Err(err) => {
+            let report = error_report(&label, "backend-detect-failed", &format!("{err:#}"));
+            return (report, Vec::new());
+        }
+    };
+    let report = match build_report(&backends, &policy, &profile).await {
+        Ok(report) => report,
+        Err(err) => error_report(
+            &backends_label(&backends),
+            "check-failed",
+            &format!("{err:#}"),
+        ),
+    };
+    (report, backends)
+}
+
+/// Re-check every `interval` and serve the latest metrics on `listen` until
+/// SIGINT/SIGTERM. Scrapes never trigger a check themselves.
+async fn serve(
+    cli: &Cli,
+    policy_path: &Path,
+    interval: Duration,
+    listen: SocketAddr,
+) -> Result<ExitStatus> {
+    let listener = TcpListener::bind(listen)
+        .await
+        .with_context(|| format!("failed to listen on {listen}"))?;
+    info!(%listen, "serving metrics");
+
+    let body = Arc::new(Mutex::new(String::new()));
+    let server = tokio::spawn({
+        let body = Arc::clone(&body);
+        async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(err) => {
+                        warn!(error = ?err, "failed to accept metrics connection");
+                        continue;
+                    }
+                };
+                let body = body.lock().map(|b| b.clone()).unwrap_or_default();
+                tokio::spawn(async move {
+                    if let Err(err) = metrics::respond(stream, &body).await {
+                        debug!(error = ?err, "metrics request failed");
+                    }
+                });
+            }
+        }
+    });
+
+    let mut sigterm =
+        signal(SignalKind::terminate()).context("failed to install SIGTERM handler")?;
+    let mut last = None;
+    loop {
+        let (report, _) = check_pass(cli, policy_path).await;
+        let text = metrics_text(&report).await;
+        if let Ok(mut body) = body.lock() {
+            *body = text;
+        }
+        if last != Some(report.overall) {
+            info!(overall = report.overall.as_str(), "status changed");
+            last = Some(report.overall);
+        }
+
+        tokio::select! {
+            _ = time::sleep(interval) => {}
+            _ = tokio::signal::ctrl_c() => break,
+            _ = sigterm.recv() => break,
+        }
+    }
+
+    server.abort();
+    Ok(ExitStatus::Ok)
+}
+
+/// Metrics for `report` plus detection of the built-in backends on this
+/// host and the latency of the commands run so far.
+async fn metrics_text(report: &StatusReport) -> String {
+    let mut detected = Vec::new();
+    for b in builtin_backends() {
+        detected.push((b.name().to_string(), b.detect().await.unwrap_or(false)));
+    }
+    metrics::render(report, &detected, &cmd::command_stats())
+}
+
+async fn write_metrics(report: &StatusReport, textfile: Option<&Path>) -> Result<()> {
+    let text = metrics_text(report).await;
+    match textfile {
+        Some(path) => statusfile::write_atomic(path, text.as_bytes(), 0o644),
+        None => {
+            print!("{text}");
+            Ok(())
+        }
+    }
+}
+
+type MonitorCommand = (&'static str, &'static [&'static str]);
+
+/// The change monitor `watch` follows (`nft monitor`, `dbus-monitor`, ...).
+/// When it cannot be started or exits it is restarted after a delay that
+/// doubles up to [`ChangeMonitor::MAX_BACKOFF`]; interval checks go on
+/// meanwhile.
+struct ChangeMonitor {
+    command: Option<MonitorCommand>,
+    events: Option<LineStream>,
+    started: Instant,
+    restart_at: Option<Instant>,
+    backoff: Duration,
+}
+
+impl Default for ChangeMonitor {
+    fn default() -> Self {
+        Self {
+            command: None,
+            events: None,
+            started: Instant::now(),
+            restart_at: None,
+            backoff: Self::MIN_BACKOFF,
+        }
+    }
+}
+
+impl ChangeMonitor {
+    const MIN_BACKOFF: Duration = Duration::from_secs(1);
+    const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+    /// Switch to `wanted` if it differs from the current command.
+    fn follow(&mut self, wanted: Option<MonitorCommand>) {
+        if wanted == self.command {
+            return;
+        }
+        self.command = wanted;
+        self.events = None;
+        self.backoff = Self::MIN_BACKOFF;
+        self.restart_at = wanted.map(|_| Instant::now());
+    }
+
+    /// Resolves when a re-check is due: after a burst of change events, or
+    /// once a monitor that was down has been restarted (changes made in
+    /// between were missed). Never resolves without a monitor command.
+    async fn changed(&mut self) {
+        loop {
+            if let Some(events) = &mut self.events {
+                if events.next_line().await.is_some() {
+                    debug!("firewall change event; re-checking");
+                    self.backoff = Self::MIN_BACKOFF;
+                    // coalesce bursts (a reload emits many events) into one re-check
+                    while let Ok(Some(_)) =
+                        time::timeout(Duration::from_millis(250), events.next_line()).await
+                    {
+                    }
+                    return;
+                }
+                if self.started.elapsed() >= Self::MAX_BACKOFF {
+                    self.backoff = Self::MIN_BACKOFF;
+                }
+                warn!(restart_in = ?self.backoff, "change monitor exited");
+                self.events = None;
+                self.schedule_restart();
+                continue;
+            }
+            let (Some((program, args)), Some(at)) = (self.command, self.restart_at) else {
+                return std::future::pending().await;
+            };
+            time::sleep_until(at.into()).await;
+            match spawn_lines(program, args) {
+                Ok(stream) => {
+                    info!(program, "change monitor started");
+                    self.events = Some(stream);
+                    self.started = Instant::now();
+                    self.restart_at = None;
+                    return;
+                }
+                Err(err) => {
+                    warn!(error = ?err, restart_in = ?self.backoff, "change monitor unavailable; using interval checks until it starts");
+                    self.schedule_restart();
+                }
+            }
+        }
+    }
+
+    fn schedule_restart(&mut self) {
+        self.restart_at = Some(Instant::now() + self.backoff);
+        self.backoff = (self.backoff * 2).min(Self::MAX_BACKOFF);
+    }
+}
+
+/// Write the report to `--output`, if given.
+fn save_report(out: &OutputArgs, report: &StatusReport) -> Result<()> {
+    let Some(path) = &out.output else {
+        return Ok(());
+    };
+    let mut json = serde_json::to_string_pretty(report)?;
+    json.push('\n');
+    statusfile::write_atomic(path, json.as_bytes(), out.mode)
+}
+
+/// Apply `--profile`, else the first profile whose match rules hit the
+/// current network, else `default`. Returns the resolved policy and the
+/// profile name.
+async fn resolve_profile(policy: &Policy, requested: Option<&str>) -> Result<(Policy, String)> {
+    let name = match requested {
+        Some(name) => name.to_string(),
+        None if policy.has_match_rules() => {
+            let net = network::detect().await;
+            debug!(?net, "network context for profile selection");
+            policy
+                .matching_profile(&net)
+                .unwrap_or(DEFAULT_PROFILE)
+                .to_string()
+        }
+        None => DEFAULT_PROFILE.to_string(),
+    };
+    Ok((policy.with_profile(&name)?, name))
+}
+
+/// The backends to evaluate: the one forced by `--backend` or a recording,
+/// else every entry of the policy's `backends` list, else the single backend
+/// from `backend` / auto-detection.
+async fn select_backends(
+    choice: &BackendChoice,
+    policy: &Policy,
+    snapshot_from: Option<&Path>,
+) -> Result<Vec<Box<dyn Backend>>> {
+    let names = policy.backends.as_deref().unwrap_or_default();
+    if *choice != BackendChoice::Auto || snapshot_from.is_some() || names.is_empty() {
+        let backend = select_backend(choice, policy.backend.as_deref(), snapshot_from).await?;
+        return Ok(vec![backend]);
     }
+
+    let mut backends: Vec<Box<dyn Backend>> = Vec::new();
+    for name in names {
+        if backends.iter().any(|b| b.name() == name) {
+            continue;
+        }
+        let backend = backend_from_name(name)
+            .ok_or_else(|| anyhow!("unsupported backend from policy: {name}"))?;
+        backends.push(backend);
+    }
+    Ok(backends)
 }
 
 async fn select_backend(
-    choice: BackendChoice,
+    choice: &BackendChoice,
     policy_backend: Option<&str>,
+    snapshot_from: Option<&Path>,
 ) -> Result<Box<dyn Backend>> {
+    if let Some(dir) = snapshot_from {
+        // the recording decides the backend unless one is forced
+        let name = match choice {
+            BackendChoice::Auto => backends::file::recorded_backend(dir)?,
+            forced => backend_label(forced, None),
+        };
+        let inner = backend_from_name(&name)
+            .ok_or_else(|| anyhow!("unsupported recorded backend: {name}"))?;
+        return Ok(Box::new(FileBackend::load(inner, dir)?));
+    }
+
     match choice {
         BackendChoice::Firewalld => return Ok(Box::new(FirewalldBackend)),
         BackendChoice::Nftables => return Ok(Box::new(NftablesBackend)),
+        BackendChoice::Iptables => return Ok(Box::new(IptablesBackend)),
+        BackendChoice::Ufw => return Ok(Box::new(UfwBackend)),
+        BackendChoice::Plugin(name) => {
+            return backend_from_name(name).ok_or_else(|| {
+                anyhow!("backend plugin not found on PATH: {}{name}", plugin::PREFIX)
+            })
+        }
         BackendChoice::Auto => {}
     }
 
@@ -107,7 +703,8 @@ async fn select_backend(
         }
     }
 
-    for b in all_backends() {
+    // plugins are only run when named, never probed
+    for b in builtin_backends() {
         if b.detect().await.unwrap_or(false) {
             return backend_from_name(b.name())
                 .ok_or_else(|| anyhow!("unsupported backend detected: {}", b.name()));
@@ -115,7 +712,7 @@ async fn select_backend(
     }
 
     Err(anyhow!(
-        "no supported firewall backend detected (firewalld or nftables)"
+        "no supported firewall backend detected (firewalld, ufw, nftables or iptables)"
     ))
 }
 
@@ -123,21 +720,82 @@ fn backend_from_name(name: &str) -> Option<Box<dyn Backend>> {
     match name {
         "firewalld" => Some(Box::new(FirewalldBackend)),
         "nftables" => Some(Box::new(NftablesBackend)),
-        _ => None,
+        "iptables" => Some(Box::new(IptablesBackend)),
+        "ufw" => Some(Box::new(UfwBackend)),
+        _ => plugin::find(name).map(|p| Box::new(p) as Box<dyn Backend>),
     }
 }
 
-async fn build_report(backend: &dyn Backend, policy: &Policy) -> Result<StatusReport> {
-    let snap = backend.snapshot().await?;
-    let findings = backend.validate(policy, &snap).await?;
+/// Evaluate the policy against every backend and merge their findings into
+/// one report; each finding is tagged with the backend that produced it.
+async fn build_report(
+    backends: &[Box<dyn Backend>],
+    policy: &Policy,
+    profile: &str,
+) -> Result<StatusReport> {
+    let verify_baseline = policy
+        .checks
+        .as_ref()
+        .and_then(|c| c.verify_baseline)
+        .unwrap_or(false);
 
+    let mut findings = Vec::new();
+    for backend in backends {
+        let snap = backend.snapshot().await?;
+        let mut found = backend.validate(policy, &snap).await?;
+        if snap.active && verify_baseline {
+            found.extend(baseline::verify(
+                &snap.backend_name,
+                &backend.normalize(&snap),
+            ));
+        }
+        findings.extend(tagged(found, &snap));
+    }
+    apply_severity(policy, &mut findings);
+
+    let mut report = summarize(&backends_label(backends), findings);
+    report.active_profile = profile.to_string();
+    if backends.iter().any(|b| !b.is_live()) {
+        return Ok(report);
+    }
+    let past = history::load().unwrap_or_else(|err| {
+        warn!(error = ?err, "failed to load check history");
+        Vec::new()
+    });
+    history::annotate(&mut report, &past);
+    if let Err(err) = history::record(&report) {
+        warn!(error = ?err, "failed to record check history");
+    }
+    Ok(report)
+}
+
+fn tagged(mut findings: Vec<Finding>, snap: &BackendStatus) -> Vec<Finding> {
+    for f in &mut findings {
+        f.backend = Some(snap.backend_name.clone());
+    }
+    findings
+}
+
+/// Report label for the evaluated backends, e.g. `firewalld+nftables`.
+fn backends_label(backends: &[Box<dyn Backend>]) -> String {
+    backends
+        .iter()
+        .map(|b| b.name())
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+fn summarize(backend: &str, findings: Vec<Finding>) -> StatusReport {
+    let mut passed = 0u32;
     let mut warn = 0u32;
     let mut failed = 0u32;
+    let mut skipped = 0u32;
     for f in &findings {
-        match f.severity {
-            Overall::Warn => warn += 1,
-            Overall::Error => failed += 1,
-            Overall::Ok => {}
+        match f.status {
+            CheckStatus::Pass => passed += 1,
+            CheckStatus::Warn => warn += 1,
+            CheckStatus::Fail => failed += 1,
+            CheckStatus::Skipped => skipped += 1,
         }
     }
 
@@ -149,47 +807,127 @@ async fn build_report(backend: &dyn Backend, policy: &Policy) -> Result<StatusRe
         Overall::Ok
     };
 
-    Ok(StatusReport {
-        schema: 1,
+    StatusReport {
+        schema: REPORT_SCHEMA,
         overall,
-        backend: snap.backend_name.to_string(),
+        backend: backend.to_string(),
         active_profile: "default".into(),
         last_check: Utc::now(),
         summary: Summary {
             checks_total: findings.len() as u32,
+            checks_passed: passed,
             checks_warn: warn,
             checks_failed: failed,
+            checks_skipped: skipped,
         },
         findings,
-    })
+    }
 }
 
-fn emit_report(cmd: Command, report: StatusReport) -> Result<ExitStatus> {
+fn apply_severity(policy: &Policy, findings: &mut [Finding]) {
+    let Some(checks) = policy.checks.as_ref() else {
+        return;
+    };
+    // passed checks stay ok whatever severity their failure would have
+    for f in findings.iter_mut().filter(|f| f.severity != Overall::Ok) {
+        if let Some(sev) = checks.severity_for(&f.id) {
+            f.set_severity(sev);
+        }
+    }
+}
+
+async fn emit_report(cmd: Command, report: StatusReport) -> Result<ExitStatus> {
     match cmd {
-        Command::Status { json, one_line } => {
+        Command::Status {
+            json,
+            one_line,
+            out,
+        } => {
+            save_report(&out, &report)?;
             output_report(&report, json, one_line);
             Ok(report.overall.into())
         }
-        Command::Check { json } => {
-            if json {
-                println!("{}", serde_json::to_string_pretty(&report)?);
-            } else {
-                println!("{}", report.overall.as_str());
-            }
+        Command::Check { json, format, out } => {
+            save_report(&out, &report)?;
+            print_check(&report, json, format)?;
             Ok(report.overall.into())
         }
-        Command::Diff => {
+        Command::NmDispatch {
+            out, exit_status, ..
+        } => {
+            save_report(&out, &report)?;
+            output_report(&report, false, true);
+            Ok(dispatch_exit(&report, exit_status))
+        }
+        Command::Metrics { textfile } => {
+            write_metrics(&report, textfile.as_deref()).await?;
+            Ok(ExitStatus::Ok)
+        }
+        Command::Diff { .. } | Command::Baseline { .. } => {
             eprintln!(
                 "{}",
                 report
                     .findings
                     .first()
                     .map(|f| f.msg.as_str())
-                    .unwrap_or("diff unavailable")
+                    .unwrap_or("backend unavailable")
             );
             Ok(report.overall.into())
         }
-        Command::Backend { .. } => unreachable!("handled earlier"),
+        Command::Backend { .. }
+        | Command::History { .. }
+        | Command::Policy { .. }
+        | Command::Schema { .. }
+        | Command::Snapshot { .. }
+        | Command::Watch { .. }
+        | Command::Serve { .. } => {
+            unreachable!("handled earlier")
+        }
+    }
+}
+
+/// Upgrade every file the policy is merged from; files that are already
+/// current are left untouched. Nothing is written unless every file converts.
+fn migrate_policy(policy_path: &Path, write: bool) -> Result<()> {
+    let resolved = policy::resolve(policy_path)?;
+    let mut changed = Vec::new();
+    for file in resolved.files.iter().filter(|f| f.exists()) {
+        let raw = std::fs::read_to_string(file)
+            .with_context(|| format!("failed to read policy file: {}", file.display()))?;
+        let migrated = policy::v2::migrate(&raw)
+            .with_context(|| format!("failed to migrate {}", file.display()))?;
+        if migrated != raw {
+            changed.push((file, raw, migrated));
+        }
+    }
+    for (file, raw, migrated) in &changed {
+        if write {
+            let mode = std::fs::metadata(file)?.permissions().mode() & 0o7777;
+            statusfile::write_atomic(file, migrated.as_bytes(), mode)?;
+            println!("migrated {}", file.display());
+        } else {
+            let name = file.display().to_string();
+            let diff = similar::TextDiff::from_lines(raw, migrated);
+            print!("{}", diff.unified_diff().header(&name, &name));
+        }
+    }
+    if changed.is_empty() {
+        println!(
+            "{}: already at policy schema {}",
+            policy_path.display(),
+            policy::v2::LATEST
+        );
+    }
+    Ok(())
+}
+
+/// NetworkManager logs non-zero dispatcher exits as failures, so the check
+/// result is only passed through when asked for.
+fn dispatch_exit(report: &StatusReport, exit_status: bool) -> ExitStatus {
+    if exit_status {
+        report.overall.into()
+    } else {
+        ExitStatus::Ok
     }
 }
 
@@ -203,10 +941,25 @@ fn output_report(report: &StatusReport, json: bool, one_line: bool) {
     }
 }
 
-fn backend_label(choice: BackendChoice, policy_backend: Option<&str>) -> String {
+fn print_check(report: &StatusReport, json: bool, format: Option<CheckFormat>) -> Result<()> {
+    match format {
+        Some(CheckFormat::Junit) => print!("{}", export::junit(report)),
+        Some(CheckFormat::Sarif) => {
+            println!("{}", serde_json::to_string_pretty(&export::sarif(report))?)
+        }
+        None if json => println!("{}", serde_json::to_string_pretty(report)?),
+        None => println!("{}", report.overall.as_str()),
+    }
+    Ok(())
+}
+
+fn backend_label(choice: &BackendChoice, policy_backend: Option<&str>) -> String {
     match choice {
         BackendChoice::Firewalld => "firewalld".into(),
         BackendChoice::Nftables => "nftables".into(),
+        BackendChoice::Iptables => "iptables".into(),
+        BackendChoice::Ufw => "ufw".into(),
+        BackendChoice::Plugin(name) => name.clone(),
         BackendChoice::Auto => policy_backend.unwrap_or("auto").to_string(),
     }
 }
@@ -215,31 +968,206 @@ fn print_human_readable(report: &StatusReport) {
     println!("backend: {}", report.backend);
     println!("overall: {}", report.overall.as_str());
     println!("last_check: {}", report.last_check.to_rfc3339());
+    let s = &report.summary;
     println!(
-        "checks: total={} warn={} failed={}",
-        report.summary.checks_total, report.summary.checks_warn, report.summary.checks_failed
+        "checks: total={} passed={} warn={} failed={} skipped={}",
+        s.checks_total, s.checks_passed, s.checks_warn, s.checks_failed, s.checks_skipped
     );
-    for f in &report.findings {
+    // problems first, then what was verified and what could not be
+    for f in report.findings.iter().filter(|f| f.severity != Overall::Ok) {
         println!("- [{}] {}: {}", f.severity.as_str(), f.id, f.msg);
+        if let (Some(expected), Some(actual)) = (&f.expected, &f.actual) {
+            println!("    expected: {expected}");
+            println!("    actual:   {actual}");
+        }
+        if let Some(fix) = &f.remediation {
+            println!("    fix:      {fix}");
+        }
+    }
+    for status in [CheckStatus::Pass, CheckStatus::Skipped] {
+        for f in report.findings.iter().filter(|f| f.status == status) {
+            println!("- [{}] {}: {}", status.as_str(), f.id, f.msg);
+        }
     }
 }
 
 fn error_report(backend: &str, id: &str, msg: &str) -> StatusReport {
     StatusReport {
-        schema: 1,
+        schema: REPORT_SCHEMA,
         overall: Overall::Error,
         backend: backend.to_string(),
         active_profile: "default".into(),
         last_check: Utc::now(),
         summary: Summary {
             checks_total: 1,
+            checks_passed: 0,
             checks_warn: 0,
             checks_failed: 1,
+            checks_skipped: 0,
         },
-        findings: vec![crate::status::Finding {
-            id: id.into(),
-            severity: Overall::Error,
-            msg: msg.into(),
-        }],
+        findings: vec![Finding::new(id, Overall::Error, msg)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn change_monitor_is_restarted_with_backoff() {
+        let mut changes = ChangeMonitor::default();
+        changes.follow(Some(("sh", &["-c", "echo changed"])));
+
+        // started, then its event, then it exits and is restarted after 1s
+        changes.changed().await;
+        assert!(changes.events.is_some());
+        changes.changed().await;
+        let exited = Instant::now();
+        changes.changed().await;
+        assert!(exited.elapsed() >= ChangeMonitor::MIN_BACKOFF);
+        assert!(changes.events.is_some());
+        assert_eq!(changes.backoff, 2 * ChangeMonitor::MIN_BACKOFF);
+
+        // a monitor that cannot be spawned is retried as well
+        changes.follow(Some(("/nonexistent/monitor", &[])));
+        assert_eq!(changes.backoff, ChangeMonitor::MIN_BACKOFF);
+        let first = time::timeout(Duration::from_millis(200), changes.changed()).await;
+        assert!(first.is_err());
+        assert!(changes.events.is_none());
+        assert!(changes.restart_at.is_some());
+        assert_eq!(changes.backoff, 2 * ChangeMonitor::MIN_BACKOFF);
+
+        // and nothing is started without a monitor command
+        changes.follow(None);
+        assert!(time::timeout(Duration::from_millis(50), changes.changed())
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn severity_overrides_apply_to_failing_checks_only() {
+        let policy: Policy = toml::from_str(
+            r#"
+schema = 1
+[checks.severity]
+"missing-port" = "warn"
+"missing-port:22/tcp" = "error"
+"forbidden-port" = "ok"
+"#,
+        )
+        .unwrap();
+        let mut findings = vec![
+            Finding::new("missing-port:22/tcp", Overall::Warn, "22/tcp is not open"),
+            Finding::new("missing-port:80/tcp", Overall::Error, "80/tcp is not open"),
+            Finding::new("forbidden-port:23/tcp", Overall::Error, "23/tcp is open"),
+            Finding::pass("missing-port:443/tcp", "443/tcp is open"),
+            Finding::skipped("missing-port:8080/tcp", "backend inactive"),
+        ];
+        apply_severity(&policy, &mut findings);
+        let got: Vec<_> = findings.iter().map(|f| (f.status, f.severity)).collect();
+        assert_eq!(
+            got,
+            [
+                (CheckStatus::Fail, Overall::Error),
+                (CheckStatus::Warn, Overall::Warn),
+                (CheckStatus::Pass, Overall::Ok),
+                (CheckStatus::Pass, Overall::Ok),
+                (CheckStatus::Skipped, Overall::Ok),
+            ]
+        );
+    }
+
+    fn replayed(name: &str) -> Box<dyn Backend> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/snapshots")
+            .join(name);
+        let inner = builtin_backends()
+            .into_iter()
+            .find(|b| b.name() == name)
+            .unwrap();
+        Box::new(crate::backends::file::FileBackend::load(inner, &dir).unwrap())
+    }
+
+    #[tokio::test]
+    async fn merges_findings_from_every_backend() {
+        let policy: Policy = toml::from_str(
+            "schema = 1\n[checks]\nrequired_ports = [\"22/tcp\"]\nforbidden_ports = [\"80/tcp\"]\n",
+        )
+        .unwrap();
+        let backends = [replayed("ufw"), replayed("nftables")];
+        let report = build_report(&backends, &policy, "office").await.unwrap();
+        let got: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.backend.as_deref().unwrap(), f.id.as_str(), f.status))
+            .collect();
+        assert_eq!(
+            got,
+            [
+                ("ufw", "ufw-active", CheckStatus::Pass),
+                ("ufw", "missing-port:22/tcp", CheckStatus::Pass),
+                ("ufw", "forbidden-port:80/tcp", CheckStatus::Pass),
+                ("nftables", "nftables-available", CheckStatus::Pass),
+                ("nftables", "forbidden-port:80/tcp", CheckStatus::Fail),
+                ("nftables", "missing-port:22/tcp", CheckStatus::Skipped),
+            ]
+        );
+        // one failing backend fails the merged report
+        assert_eq!(report.backend, "ufw+nftables");
+        assert_eq!(report.active_profile, "office");
+        assert_eq!(report.overall, Overall::Error);
+        assert_eq!(report.summary.checks_total, 6);
+        assert_eq!(report.summary.checks_failed, 1);
+    }
+
+    #[test]
+    fn summary_counts_passed_and_skipped_checks() {
+        let summary = |findings: Vec<Finding>| {
+            let report = summarize("ufw", findings);
+            let s = report.summary;
+            (
+                report.overall,
+                [
+                    s.checks_total,
+                    s.checks_passed,
+                    s.checks_warn,
+                    s.checks_failed,
+                    s.checks_skipped,
+                ],
+            )
+        };
+        // passed and skipped checks count towards the total but keep the report ok
+        assert_eq!(
+            summary(vec![
+                Finding::pass("ufw-active", "ufw is active"),
+                Finding::pass("missing-port:22/tcp", "22/tcp is allowed"),
+                Finding::skipped("missing-fragment:tcp dport 22 accept", "no ruleset on ufw"),
+            ]),
+            (Overall::Ok, [3, 2, 0, 0, 1])
+        );
+        assert_eq!(
+            summary(vec![
+                Finding::pass("ufw-active", "ufw is active"),
+                Finding::new(
+                    "missing-port:443/tcp",
+                    Overall::Warn,
+                    "443/tcp is not allowed"
+                ),
+                Finding::skipped("missing-fragment:tcp dport 22 accept", "no ruleset on ufw"),
+            ]),
+            (Overall::Warn, [3, 1, 1, 0, 1])
+        );
+        assert_eq!(
+            summary(vec![
+                Finding::new(
+                    "missing-port:443/tcp",
+                    Overall::Warn,
+                    "443/tcp is not allowed"
+                ),
+                Finding::new("forbidden-port:23/tcp", Overall::Error, "23/tcp is allowed"),
+            ]),
+            (Overall::Error, [2, 0, 1, 1, 0])
+        );
+        assert_eq!(summary(Vec::new()), (Overall::Ok, [0, 0, 0, 0, 0]));
     }
 }
@@ -0,0 +1,264 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    backends::{Backend, BackendStatus, CommandOutput, Outputs},
+    config::Policy,
+    status::Finding,
+};
+
+/// Manifest written next to the recorded outputs.
+const MANIFEST: &str = "snapshot.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub backend: String,
+    pub captured_at: DateTime<Utc>,
+}
+
+/// Replays command outputs recorded by `snapshot export` through another
+/// backend's parser and checks. Each command's output lives in
+/// `<dir>/<slug>.out`, with optional `<slug>.err` and `<slug>.exit` (exit
+/// code, default 0); a missing `.out` means the command could not be run.
+pub struct FileBackend {
+    inner: Box<dyn Backend>,
+    dir: PathBuf,
+    outputs: Outputs,
+}
+
+impl FileBackend {
+    pub fn load(inner: Box<dyn Backend>, dir: &Path) -> Result<FileBackend> {
+        anyhow::ensure!(
+            dir.is_dir(),
+            "snapshot directory not found: {}",
+            dir.display()
+        );
+        let mut outputs = Outputs::default();
+        for argv in inner.snapshot_commands() {
+            let base = dir.join(slug(argv));
+            let Some(stdout) = read_optional(&base.with_extension("out"))? else {
+                continue;
+            };
+            let stderr = read_optional(&base.with_extension("err"))?.unwrap_or_default();
+            let code = match read_optional(&base.with_extension("exit"))? {
+                Some(s) => s.trim().parse().with_context(|| {
+                    format!(
+                        "invalid exit code in {}",
+                        base.with_extension("exit").display()
+                    )
+                })?,
+                None => 0,
+            };
+            outputs.insert(
+                argv,
+                CommandOutput {
+                    code,
+                    stdout,
+                    stderr,
+                },
+            );
+        }
+        Ok(FileBackend {
+            inner,
+            dir: dir.to_path_buf(),
+            outputs,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Backend for FileBackend {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn detect(&self) -> Result<bool> {
+        Ok(self.dir.is_dir())
+    }
+
+    fn snapshot_commands(&self) -> &'static [&'static [&'static str]] {
+        self.inner.snapshot_commands()
+    }
+
+    fn snapshot_from(&self, outputs: &Outputs) -> Result<BackendStatus> {
+        self.inner.snapshot_from(outputs)
+    }
+
+    async fn snapshot(&self) -> Result<BackendStatus> {
+        self.inner.snapshot_from(&self.outputs)
+    }
+
+    async fn validate(&self, policy: &Policy, snap: &BackendStatus) -> Result<Vec<Finding>> {
+        self.inner.validate(policy, snap).await
+    }
+
+    fn is_live(&self) -> bool {
+        false
+    }
+
+    fn normalize(&self, snap: &BackendStatus) -> String {
+        self.inner.normalize(snap)
+    }
+}
+
+/// Backend name recorded in `<dir>/snapshot.json`.
+pub fn recorded_backend(dir: &Path) -> Result<String> {
+    let path = dir.join(MANIFEST);
+    let s = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read snapshot manifest: {}", path.display()))?;
+    let manifest: Manifest = serde_json::from_str(&s)
+        .with_context(|| format!("invalid snapshot manifest: {}", path.display()))?;
+    Ok(manifest.backend)
+}
+
+/// Run the backend's snapshot commands and record their outputs under `dir`.
+/// Returns the number of commands that could be run.
+pub async fn export(backend: &dyn Backend, dir: &Path) -> Result<usize> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("failed to create directory: {}", dir.display()))?;
+
+    let commands = backend.snapshot_commands();
+    let outputs = Outputs::capture(commands).await;
+    let recorded = record(dir, commands, &outputs)?;
+
+    let manifest = Manifest {
+        backend: backend.name().to_string(),
+        captured_at: Utc::now(),
+    };
+    let mut json = serde_json::to_string_pretty(&manifest)?;
+    json.push('\n');
+    write(&dir.join(MANIFEST), &json)?;
+    Ok(recorded)
+}
+
+/// Write each command's files, removing ones left over from an earlier
+/// export into the same directory (a stale `.exit` would replay a recovered
+/// command as failed). Returns the number of commands recorded.
+fn record(dir: &Path, commands: &[&[&str]], outputs: &Outputs) -> Result<usize> {
+    let mut recorded = 0;
+    for argv in commands {
+        let base = dir.join(slug(argv));
+        let (out, err, exit) = (
+            base.with_extension("out"),
+            base.with_extension("err"),
+            base.with_extension("exit"),
+        );
+        let Some(output) = outputs.get(argv) else {
+            for stale in [&out, &err, &exit] {
+                remove_optional(stale)?;
+            }
+            continue;
+        };
+        write(&out, &output.stdout)?;
+        if output.stderr.is_empty() {
+            remove_optional(&err)?;
+        } else {
+            write(&err, &output.stderr)?;
+        }
+        if output.code == 0 {
+            remove_optional(&exit)?;
+        } else {
+            write(&exit, &format!("{}\n", output.code))?;
+        }
+        recorded += 1;
+    }
+    Ok(recorded)
+}
+
+/// File stem for a command: `nft -j list ruleset` -> `nft-j-list-ruleset`.
+fn slug(argv: &[&str]) -> String {
+    let mut out = String::new();
+    for c in argv.join(" ").chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c);
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    out.trim_end_matches('-').to_string()
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(s) => Ok(Some(s)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("failed to read: {}", path.display())),
+    }
+}
+
+fn remove_optional(path: &Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(err).with_context(|| format!("failed to remove: {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn write(path: &Path, contents: &str) -> Result<()> {
+    std::fs::write(path, contents).with_context(|| format!("failed to write: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMANDS: &[&[&str]] = &[
+        &["firewall-cmd", "--state"],
+        &["firewall-cmd", "--get-zones"],
+    ];
+
+    fn output(code: i32, stdout: &str, stderr: &str) -> CommandOutput {
+        CommandOutput {
+            code,
+            stdout: stdout.into(),
+            stderr: stderr.into(),
+        }
+    }
+
+    fn files(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn re_export_removes_stale_files() {
+        let dir = std::env::temp_dir().join(format!("sentinel-export-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut failed = Outputs::default();
+        failed.insert(
+            COMMANDS[0],
+            output(252, "not running\n", "FirewallD is not running\n"),
+        );
+        failed.insert(COMMANDS[1], output(0, "public\n", ""));
+        assert_eq!(record(&dir, COMMANDS, &failed).unwrap(), 2);
+        assert_eq!(
+            files(&dir),
+            [
+                "firewall-cmd-get-zones.out",
+                "firewall-cmd-state.err",
+                "firewall-cmd-state.exit",
+                "firewall-cmd-state.out",
+            ]
+        );
+
+        // recovered, and the zone listing could not be run this time
+        let mut recovered = Outputs::default();
+        recovered.insert(COMMANDS[0], output(0, "running\n", ""));
+        assert_eq!(record(&dir, COMMANDS, &recovered).unwrap(), 1);
+        assert_eq!(files(&dir), ["firewall-cmd-state.out"]);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("firewall-cmd-state.out")).unwrap(),
+            "running\n"
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,19 +1,26 @@
 use anyhow::Result;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use tracing::debug;
 
 use crate::{
-    backends::{Backend, BackendStatus},
+    backends::{
+        skip_rules, Backend, BackendStatus, Outputs, FRAGMENT_RULES, PORT_RULES, SERVICE_RULES,
+    },
     cmd::run_timeout,
-    config::Policy,
-    status::{Finding, Overall},
+    config::{parse_port_spec, Policy, ZoneChecks},
+    status::{Evidence, Finding, Overall},
 };
 
 pub struct FirewalldBackend;
 
+const STATE: &[&str] = &["firewall-cmd", "--state"];
+const DEFAULT_ZONE: &[&str] = &["firewall-cmd", "--get-default-zone"];
+const ACTIVE_ZONES: &[&str] = &["firewall-cmd", "--get-active-zones"];
+const ALL_ZONES: &[&str] = &["firewall-cmd", "--list-all-zones"];
+
 #[async_trait::async_trait]
 impl Backend for FirewalldBackend {
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         "firewalld"
     }
 
@@ -27,116 +34,607 @@ impl Backend for FirewalldBackend {
         }
     }
 
-    async fn snapshot(&self) -> Result<BackendStatus> {
-        let active = match run_timeout("firewall-cmd", &["--state"], 1500).await {
-            Ok((_code, out, _err)) => out.trim() == "running",
-            Err(err) => {
-                debug!(error = ?err, "firewalld state check failed");
-                false
-            }
-        };
+    fn snapshot_commands(&self) -> &'static [&'static [&'static str]] {
+        &[STATE, DEFAULT_ZONE, ACTIVE_ZONES, ALL_ZONES]
+    }
 
-        let default_zone = match run_timeout("firewall-cmd", &["--get-default-zone"], 1500).await {
-            Ok((_code, out, _err)) => out.trim().to_string(),
-            Err(err) => {
-                debug!(error = ?err, "failed to read firewalld default zone");
-                "unknown".to_string()
-            }
-        };
+    fn snapshot_from(&self, outputs: &Outputs) -> Result<BackendStatus> {
+        let stdout = |argv: &[&str]| outputs.get(argv).map(|o| o.stdout.as_str());
 
-        let list_args = if default_zone.is_empty() || default_zone == "unknown" {
-            vec!["--list-all"]
-        } else {
-            vec!["--zone", default_zone.as_str(), "--list-all"]
-        };
+        let active = stdout(STATE).is_some_and(|out| out.trim() == "running");
+        let default_zone = stdout(DEFAULT_ZONE)
+            .map(str::trim)
+            .unwrap_or("unknown")
+            .to_string();
+        let active_zones = stdout(ACTIVE_ZONES).unwrap_or_default();
+        let raw = stdout(ALL_ZONES).unwrap_or_default().to_string();
 
-        let raw = match run_timeout("firewall-cmd", &list_args, 2000).await {
-            Ok((_code, out, _err)) => out,
-            Err(err) => {
-                debug!(error = ?err, "failed to get firewalld listing");
-                String::new()
-            }
-        };
+        let mut facts = vec![("default_zone".into(), default_zone)];
+        facts.extend(parse_active_zones(active_zones));
 
         Ok(BackendStatus {
-            backend_name: self.name(),
+            backend_name: self.name().to_string(),
             active,
-            facts: vec![("default_zone".into(), default_zone)],
+            facts,
             raw,
+            ruleset: None,
+            iptables: None,
         })
     }
 
     async fn validate(&self, policy: &Policy, snap: &BackendStatus) -> Result<Vec<Finding>> {
         let mut findings = Vec::new();
         let checks = policy.checks.as_ref();
-        let parsed = parse_zone_listing(&snap.raw);
+        let zones = parse_zones(&snap.raw);
 
         if checks
             .and_then(|c| c.require_firewall_active)
             .unwrap_or(true)
-            && !snap.active
         {
-            findings.push(Finding {
-                id: "firewalld-not-running".into(),
-                severity: Overall::Error,
-                msg: "firewalld is not running (firewall-cmd --state != running)".into(),
-            });
-            return Ok(findings);
-        }
-
-        if let Some(req_services) = checks.and_then(|c| c.required_services.as_ref()) {
-            for s in req_services {
-                if !parsed.services.contains(s) {
-                    findings.push(Finding {
-                        id: format!("missing-service:{s}"),
-                        severity: Overall::Warn,
-                        msg: format!("required service not found in zone listing: {s}"),
-                    });
+            if !snap.active {
+                findings.push(
+                    Finding::new(
+                        "firewalld-not-running",
+                        Overall::Error,
+                        "firewalld is not running (firewall-cmd --state != running)",
+                    )
+                    .with_values("running", "not running")
+                    .with_remediation("systemctl enable --now firewalld"),
+                );
+                findings.extend(skip_rules(
+                    checks,
+                    &[SERVICE_RULES, PORT_RULES].concat(),
+                    "firewalld is not running",
+                ));
+                for scoped in checks.and_then(|c| c.zones.as_ref()).into_iter().flatten() {
+                    if let Some(zone) = &scoped.zone {
+                        findings.extend(skip_zone(scoped, zone, "firewalld is not running"));
+                    }
                 }
+                findings.extend(skip_rules(checks, FRAGMENT_RULES, NO_FRAGMENTS));
+                return Ok(findings);
             }
+            findings.push(Finding::pass("firewalld-running", "firewalld is running"));
         }
 
-        if let Some(req_ports) = checks.and_then(|c| c.required_ports.as_ref()) {
-            for p in req_ports {
-                if !parsed.ports.contains(p) {
-                    findings.push(Finding {
-                        id: format!("missing-port:{p}"),
-                        severity: Overall::Warn,
-                        msg: format!("required port not found in zone listing: {p}"),
-                    });
+        let default_zone = fact(snap, "default_zone").unwrap_or("unknown");
+        let empty = ZoneListing::default();
+        let default_listing = zones.get(default_zone).unwrap_or(&empty);
+
+        for (rule, items) in [
+            (
+                ZoneRule::Service,
+                checks.and_then(|c| c.required_services.as_ref()),
+            ),
+            (
+                ZoneRule::Port,
+                checks.and_then(|c| c.required_ports.as_ref()),
+            ),
+        ] {
+            for item in items.into_iter().flatten() {
+                findings.push(rule.required(
+                    rule.id("missing", None, item),
+                    default_zone,
+                    default_listing,
+                    item,
+                ));
+            }
+        }
+
+        // deny-lists apply to every zone that can see traffic
+        let mut exposed: Vec<&str> = fact(snap, "active_zones")
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect();
+        if !exposed.contains(&default_zone) {
+            exposed.push(default_zone);
+        }
+
+        for (rule, items) in [
+            (
+                ZoneRule::Service,
+                checks.and_then(|c| c.forbidden_services.as_ref()),
+            ),
+            (
+                ZoneRule::Port,
+                checks.and_then(|c| c.forbidden_ports.as_ref()),
+            ),
+        ] {
+            for item in items.into_iter().flatten() {
+                findings.push(rule.forbidden(
+                    rule.id("forbidden", None, item),
+                    &zones,
+                    &exposed,
+                    item,
+                ));
+            }
+        }
+
+        for scoped in checks.and_then(|c| c.zones.as_ref()).into_iter().flatten() {
+            let zone = match (&scoped.zone, &scoped.interface) {
+                (Some(zone), _) => zone.clone(),
+                (None, Some(iface)) => match zone_of_interface(snap, &zones, iface) {
+                    Some(zone) => zone.to_string(),
+                    None => {
+                        // firewalld puts unbound interfaces in the default zone,
+                        // but a typo or a missing interface looks the same
+                        findings.push(
+                            Finding::new(
+                                format!("unbound-interface:{iface}"),
+                                Overall::Warn,
+                                format!(
+                                    "interface {iface} is not bound to any zone; checking the default zone {default_zone}"
+                                ),
+                            )
+                            .with_evidence(
+                                Evidence::rule(format!("interface = {iface:?}")).in_zone(default_zone),
+                            )
+                            .with_values(
+                                format!("{iface} bound to a zone"),
+                                format!("not bound, default zone {default_zone} assumed"),
+                            )
+                            .with_remediation(format!(
+                                "check the interface name, or bind it: firewall-cmd --permanent --zone=<zone> --add-interface={iface} && firewall-cmd --reload"
+                            )),
+                        );
+                        default_zone.to_string()
+                    }
+                },
+                (None, None) => {
+                    findings.push(
+                        Finding::new(
+                            "firewalld-zone-check-invalid",
+                            Overall::Warn,
+                            "zone check needs `zone` or `interface`",
+                        )
+                        .with_remediation(
+                            "set `zone` or `interface` in the [[checks.zones]] entry",
+                        ),
+                    );
+                    continue;
+                }
+            };
+
+            let Some(listing) = zones.get(&zone) else {
+                findings.push(
+                    Finding::new(
+                        format!("missing-zone:{zone}"),
+                        Overall::Warn,
+                        format!("zone not found in firewalld configuration: {zone}"),
+                    )
+                    .with_remediation(format!(
+                        "firewall-cmd --permanent --new-zone={zone} && firewall-cmd --reload"
+                    )),
+                );
+                findings.extend(skip_zone(scoped, &zone, &format!("zone {zone} not found")));
+                continue;
+            };
+
+            for (rule, items) in [
+                (ZoneRule::Service, &scoped.required_services),
+                (ZoneRule::Port, &scoped.required_ports),
+            ] {
+                for item in items.iter().flatten() {
+                    findings.push(rule.required(
+                        rule.id("missing", Some(&zone), item),
+                        &zone,
+                        listing,
+                        item,
+                    ));
+                }
+            }
+
+            for (rule, items) in [
+                (ZoneRule::Service, &scoped.forbidden_services),
+                (ZoneRule::Port, &scoped.forbidden_ports),
+            ] {
+                for item in items.iter().flatten() {
+                    findings.push(rule.forbidden(
+                        rule.id("forbidden", Some(&zone), item),
+                        &zones,
+                        &[zone.as_str()],
+                        item,
+                    ));
                 }
             }
         }
 
+        findings.extend(skip_rules(checks, FRAGMENT_RULES, NO_FRAGMENTS));
         Ok(findings)
     }
+
+    fn monitor_command(&self) -> Option<(&'static str, &'static [&'static str])> {
+        Some((
+            "dbus-monitor",
+            &[
+                "--system",
+                "type='signal',interface='org.fedoraproject.FirewallD1'",
+                "type='signal',interface='org.fedoraproject.FirewallD1.zone'",
+            ],
+        ))
+    }
+
+    fn normalize(&self, snap: &BackendStatus) -> String {
+        normalize_zones(&snap.raw)
+    }
+}
+
+const NO_FRAGMENTS: &str = "rule fragments are matched by the nftables and iptables backends";
+
+/// Skipped results for the rule lists of a zone check that could not be
+/// evaluated.
+fn skip_zone(scoped: &ZoneChecks, zone: &str, why: &str) -> Vec<Finding> {
+    let mut skipped = Vec::new();
+    for (kind, list) in [
+        ("missing-service", &scoped.required_services),
+        ("missing-port", &scoped.required_ports),
+        ("forbidden-service", &scoped.forbidden_services),
+        ("forbidden-port", &scoped.forbidden_ports),
+    ] {
+        for item in list.iter().flatten() {
+            skipped.push(Finding::skipped(format!("{kind}:{zone}/{item}"), why));
+        }
+    }
+    skipped
 }
 
 #[derive(Default)]
 struct ZoneListing {
+    interfaces: HashSet<String>,
+    sources: HashSet<String>,
     services: HashSet<String>,
     ports: HashSet<String>,
 }
 
-fn parse_zone_listing(raw: &str) -> ZoneListing {
-    let mut result = ZoneListing::default();
+impl ZoneListing {
+    /// The listed port entry that opens `spec` (`<port>/<proto>`): the spec
+    /// itself or a range containing it, such as `3000-3400/tcp`.
+    fn port_entry(&self, spec: &str) -> Option<&str> {
+        if let Some(listed) = self.ports.get(spec) {
+            return Some(listed);
+        }
+        let (port, proto) = parse_port_spec(spec)?;
+        self.ports
+            .iter()
+            .find(|listed| {
+                let Some((range, listed_proto)) = listed.split_once('/') else {
+                    return false;
+                };
+                let Some((lo, hi)) = range.split_once('-') else {
+                    return false;
+                };
+                listed_proto == proto
+                    && matches!(
+                        (lo.parse::<u16>(), hi.parse::<u16>()),
+                        (Ok(lo), Ok(hi)) if (lo..=hi).contains(&port)
+                    )
+            })
+            .map(String::as_str)
+    }
+}
+
+/// The service and port lists that firewalld checks assert on.
+#[derive(Clone, Copy)]
+enum ZoneRule {
+    Service,
+    Port,
+}
+
+impl ZoneRule {
+    fn name(self) -> &'static str {
+        match self {
+            ZoneRule::Service => "service",
+            ZoneRule::Port => "port",
+        }
+    }
+
+    /// `missing-service:ssh`, `forbidden-port:public/23/tcp`, ...
+    fn id(self, prefix: &str, zone: Option<&str>, item: &str) -> String {
+        match zone {
+            Some(zone) => format!("{prefix}-{}:{zone}/{item}", self.name()),
+            None => format!("{prefix}-{}:{item}", self.name()),
+        }
+    }
+
+    fn listed(self, listing: &ZoneListing) -> &HashSet<String> {
+        match self {
+            ZoneRule::Service => &listing.services,
+            ZoneRule::Port => &listing.ports,
+        }
+    }
+
+    /// The zone entry that enables `item`; ports also match listed ranges, for
+    /// required and forbidden ports alike.
+    fn entry<'a>(self, listing: &'a ZoneListing, item: &str) -> Option<&'a str> {
+        match self {
+            ZoneRule::Service => listing.services.get(item).map(String::as_str),
+            ZoneRule::Port => listing.port_entry(item),
+        }
+    }
+
+    /// `services: dhcpv6-client ssh` line of `firewall-cmd --list-all`.
+    fn snippet(self, listing: &ZoneListing) -> String {
+        let mut items: Vec<&str> = self.listed(listing).iter().map(String::as_str).collect();
+        items.sort_unstable();
+        format!("{}s: {}", self.name(), items.join(" "))
+    }
+
+    fn required(self, id: String, zone: &str, listing: &ZoneListing, item: &str) -> Finding {
+        let name = self.name();
+        let state = match self {
+            ZoneRule::Service => "enabled",
+            ZoneRule::Port => "open",
+        };
+        let evidence = Evidence::rule(item)
+            .in_zone(zone)
+            .with_snippet(self.snippet(listing));
+        if let Some(entry) = self.entry(listing, item) {
+            let via = if entry == item {
+                String::new()
+            } else {
+                format!(" (via {entry})")
+            };
+            return Finding::pass(
+                id,
+                format!("required {name} {state} in zone {zone}: {item}{via}"),
+            )
+            .with_evidence(evidence);
+        }
+        Finding::new(
+            id,
+            Overall::Warn,
+            format!("required {name} not found in zone {zone}: {item}"),
+        )
+        .with_evidence(evidence)
+        .with_values(
+            format!("{name} {item} {state} in zone {zone}"),
+            format!("not listed in zone {zone}"),
+        )
+        .with_remediation(format!(
+            "firewall-cmd --permanent --zone={zone} --add-{name}={item} && firewall-cmd --reload"
+        ))
+    }
+
+    /// `item` must not be enabled in any of `names`.
+    fn forbidden(
+        self,
+        id: String,
+        zones: &BTreeMap<String, ZoneListing>,
+        names: &[&str],
+        item: &str,
+    ) -> Finding {
+        let name = self.name();
+        let (state, negated) = match self {
+            ZoneRule::Service => ("enabled", "not enabled"),
+            ZoneRule::Port => ("open", "closed"),
+        };
+        let hits: Vec<(&str, &ZoneListing, &str)> = names
+            .iter()
+            .filter_map(|zone| {
+                let listing = zones.get(*zone)?;
+                Some((*zone, listing, self.entry(listing, item)?))
+            })
+            .collect();
+
+        if hits.is_empty() {
+            return Finding::pass(
+                id,
+                format!(
+                    "forbidden {name} {negated} in zone {}: {item}",
+                    names.join(", ")
+                ),
+            )
+            .with_evidence(Evidence::rule(item).in_zone(names.join(", ")));
+        }
+
+        let hit_zones: Vec<&str> = hits.iter().map(|(zone, _, _)| *zone).collect();
+        let snippet: Vec<String> = hits
+            .iter()
+            .map(|(zone, listing, _)| format!("{zone}: {}", self.snippet(listing)))
+            .collect();
+        let mut fix: Vec<String> = hits
+            .iter()
+            .map(|(zone, _, entry)| {
+                format!("firewall-cmd --permanent --zone={zone} --remove-{name}={entry}")
+            })
+            .collect();
+        fix.push("firewall-cmd --reload".into());
+
+        Finding::new(
+            id,
+            Overall::Error,
+            format!(
+                "forbidden {name} {state} in zone {}: {item}",
+                hit_zones.join(", ")
+            ),
+        )
+        .with_evidence(
+            Evidence::rule(item)
+                .in_zone(hit_zones.join(", "))
+                .with_snippet(snippet.join("\n")),
+        )
+        .with_values(
+            format!("{name} {item} {negated} in zone {}", names.join(", ")),
+            format!("{state} in zone {}", hit_zones.join(", ")),
+        )
+        .with_remediation(fix.join(" && "))
+    }
+}
+
+/// Parse `firewall-cmd --list-all-zones`: an unindented `<zone> [(default, active)]`
+/// header followed by indented `key: values` lines.
+fn parse_zones(raw: &str) -> BTreeMap<String, ZoneListing> {
+    let mut zones = BTreeMap::new();
+    let mut current: Option<String> = None;
 
     for line in raw.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) {
+            let name = line
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            zones
+                .entry(name.clone())
+                .or_insert_with(ZoneListing::default);
+            current = Some(name);
+            continue;
+        }
+        let Some(zone) = current.as_ref().and_then(|z| zones.get_mut(z)) else {
+            continue;
+        };
+
         let line = line.trim();
-        if let Some(rest) = line.strip_prefix("services:") {
-            for svc in rest.split_whitespace() {
-                if !svc.is_empty() {
-                    result.services.insert(svc.to_string());
-                }
-            }
+        let (set, rest) = if let Some(rest) = line.strip_prefix("interfaces:") {
+            (&mut zone.interfaces, rest)
+        } else if let Some(rest) = line.strip_prefix("sources:") {
+            (&mut zone.sources, rest)
+        } else if let Some(rest) = line.strip_prefix("services:") {
+            (&mut zone.services, rest)
         } else if let Some(rest) = line.strip_prefix("ports:") {
-            for port in rest.split_whitespace() {
-                if !port.is_empty() {
-                    result.ports.insert(port.to_string());
+            (&mut zone.ports, rest)
+        } else {
+            continue;
+        };
+        set.extend(rest.split_whitespace().map(String::from));
+    }
+
+    zones
+}
+
+/// Canonical form of `--list-all-zones`: zones sorted by name, runtime markers
+/// such as `(active)` and the interfaces bound at runtime (which come and go
+/// with NetworkManager and VPNs) dropped, and multi-valued keys sorted.
+fn normalize_zones(raw: &str) -> String {
+    let mut zones: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut current: Option<String> = None;
+    let mut in_rich_rules = false;
+
+    for line in raw.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) {
+            let name = line
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            zones.entry(name.clone()).or_default();
+            current = Some(name);
+            in_rich_rules = false;
+            continue;
+        }
+        let Some(lines) = current.as_ref().and_then(|z| zones.get_mut(z)) else {
+            continue;
+        };
+
+        let line = line.trim();
+        match line.split_once(':') {
+            Some((key, rest)) if !in_rich_rules || !key.contains(' ') || key == "rich rules" => {
+                in_rich_rules = key == "rich rules";
+                if key == "interfaces" {
+                    continue;
                 }
+                let mut values: Vec<&str> = rest.split_whitespace().collect();
+                values.sort_unstable();
+                lines.push(
+                    format!("{key}: {}", values.join(" "))
+                        .trim_end()
+                        .to_string(),
+                );
             }
+            _ => lines.push(format!("rich rule: {line}")),
+        }
+    }
+
+    let mut out = String::new();
+    for (name, mut lines) in zones {
+        lines.sort();
+        out.push_str(&name);
+        out.push('\n');
+        for l in lines {
+            out.push_str("  ");
+            out.push_str(&l);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Parse `firewall-cmd --get-active-zones` into facts: `active_zones`,
+/// plus `interface:<name>` / `source:<addr>` bindings mapped to their zone.
+fn parse_active_zones(raw: &str) -> Vec<(String, String)> {
+    let mut facts = Vec::new();
+    let mut active = Vec::new();
+    let mut current = None;
+
+    for line in raw.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) {
+            let name = line.trim().to_string();
+            active.push(name.clone());
+            current = Some(name);
+            continue;
+        }
+        let Some(zone) = &current else {
+            continue;
+        };
+        let line = line.trim();
+        let (kind, rest) = if let Some(rest) = line.strip_prefix("interfaces:") {
+            ("interface", rest)
+        } else if let Some(rest) = line.strip_prefix("sources:") {
+            ("source", rest)
+        } else {
+            continue;
+        };
+        for item in rest.split_whitespace() {
+            facts.push((format!("{kind}:{item}"), zone.clone()));
         }
     }
 
-    result
+    facts.insert(0, ("active_zones".into(), active.join(" ")));
+    facts
+}
+
+fn fact<'a>(snap: &'a BackendStatus, key: &str) -> Option<&'a str> {
+    snap.facts
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
+
+fn zone_of_interface<'a>(
+    snap: &'a BackendStatus,
+    zones: &'a BTreeMap<String, ZoneListing>,
+    iface: &str,
+) -> Option<&'a str> {
+    fact(snap, &format!("interface:{iface}")).or_else(|| {
+        zones
+            .iter()
+            .find(|(_, z)| z.interfaces.contains(iface))
+            .map(|(name, _)| name.as_str())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_ignores_runtime_state() {
+        let home = "public (default, active)\n  target: default\n  interfaces: eth0\n  services: ssh dhcpv6-client\n  ports: 8080/tcp\n";
+        let vpn = "public (default, active)\n  target: default\n  interfaces: wg0 eth0\n  services: dhcpv6-client ssh\n  ports: 8080/tcp\n";
+        let normalized = normalize_zones(home);
+        assert_eq!(normalized, normalize_zones(vpn));
+        assert_eq!(
+            normalized,
+            "public\n  ports: 8080/tcp\n  services: dhcpv6-client ssh\n  target: default\n"
+        );
+    }
 }
@@ -0,0 +1,629 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::{
+    backends::{
+        skip_rules, Backend, BackendStatus, Outputs, FRAGMENT_RULES, PORT_RULES, SERVICE_RULES,
+    },
+    cmd::run_timeout,
+    config::{parse_port_spec, Checks, IptChecks, IptRuleCheck, Policy},
+    status::{Evidence, Finding, Overall},
+};
+
+pub mod ruleset;
+
+pub use ruleset::{Hit, Ruleset};
+
+/// Parsed rulesets per address family; `None` when the family's save
+/// command failed or produced unparsable output.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Rulesets {
+    pub ipv4: Option<Ruleset>,
+    pub ipv6: Option<Ruleset>,
+}
+
+impl Rulesets {
+    fn families(&self) -> Vec<(&'static str, &Ruleset)> {
+        let mut out = Vec::new();
+        if let Some(r) = &self.ipv4 {
+            out.push(("ipv4", r));
+        }
+        if let Some(r) = &self.ipv6 {
+            out.push(("ipv6", r));
+        }
+        out
+    }
+}
+
+pub struct IptablesBackend;
+
+const SAVE_V4: &[&str] = &["iptables-save"];
+const SAVE_V6: &[&str] = &["ip6tables-save"];
+
+#[async_trait::async_trait]
+impl Backend for IptablesBackend {
+    fn name(&self) -> &str {
+        "iptables"
+    }
+
+    async fn detect(&self) -> Result<bool> {
+        match run_timeout("iptables-save", &[], 2000).await {
+            Ok((code, out, _err)) => Ok(code == 0 && out.lines().any(|l| l.starts_with('*'))),
+            Err(err) => {
+                debug!(error = ?err, "iptables detection failed");
+                Ok(false)
+            }
+        }
+    }
+
+    fn snapshot_commands(&self) -> &'static [&'static [&'static str]] {
+        &[SAVE_V4, SAVE_V6]
+    }
+
+    fn snapshot_from(&self, outputs: &Outputs) -> Result<BackendStatus> {
+        let mut facts = vec![];
+        let mut raw = String::new();
+        let mut rulesets = Rulesets::default();
+        let mut active = false;
+
+        for (family, argv) in [("ipv4", SAVE_V4), ("ipv6", SAVE_V6)] {
+            let program = argv[0];
+            raw.push_str(&format!("# {program}\n"));
+            let Some(out) = outputs.stdout_ok(argv) else {
+                if let Some(o) = outputs.get(argv) {
+                    raw.push_str(&o.stderr);
+                }
+                continue;
+            };
+            active |= family == "ipv4";
+            raw.push_str(out);
+
+            match Ruleset::parse(out) {
+                Ok(r) if family == "ipv4" => rulesets.ipv4 = Some(r),
+                Ok(r) => rulesets.ipv6 = Some(r),
+                Err(err) => {
+                    debug!(error = ?err, program, "failed to parse iptables-save output");
+                    facts.push((format!("parse_error:{family}"), format!("{err:#}")));
+                }
+            }
+        }
+
+        Ok(BackendStatus {
+            backend_name: self.name().to_string(),
+            active,
+            facts,
+            raw,
+            ruleset: None,
+            iptables: Some(rulesets),
+        })
+    }
+
+    async fn validate(&self, policy: &Policy, snap: &BackendStatus) -> Result<Vec<Finding>> {
+        let mut findings = Vec::new();
+        let checks = policy.checks.as_ref();
+
+        let required = checks
+            .and_then(|c| c.require_firewall_active)
+            .unwrap_or(true);
+        if !snap.active {
+            if required {
+                findings.push(
+                    Finding::new(
+                        "iptables-unavailable",
+                        Overall::Error,
+                        "unable to read iptables ruleset (iptables-save failed)",
+                    )
+                    .with_evidence(Evidence::default().with_snippet(snap.raw.trim())),
+                );
+            }
+            findings.extend(skip_evaluated(checks, "iptables ruleset is unavailable"));
+            findings.extend(skip_rules(checks, SERVICE_RULES, NO_SERVICES));
+            return Ok(findings);
+        }
+        if required {
+            findings.push(Finding::pass(
+                "iptables-available",
+                "iptables ruleset is readable",
+            ));
+        }
+
+        let families = snap
+            .iptables
+            .as_ref()
+            .map(Rulesets::families)
+            .unwrap_or_default();
+        if families.is_empty() {
+            findings.push(Finding::new(
+                "iptables-parse-failed",
+                Overall::Error,
+                "unable to parse iptables-save output",
+            ));
+            findings.extend(skip_evaluated(
+                checks,
+                "iptables-save output could not be parsed",
+            ));
+            findings.extend(skip_rules(checks, SERVICE_RULES, NO_SERVICES));
+            return Ok(findings);
+        }
+
+        if let Some(frags) = checks.and_then(|c| c.required_fragments.as_ref()) {
+            for f in frags {
+                let id = format!("missing-fragment:{f}");
+                let hit = families
+                    .iter()
+                    .find_map(|(family, r)| r.find_fragment(f).map(|hit| (*family, hit)));
+                findings.push(match hit {
+                    Some((family, hit)) => {
+                        Finding::pass(id, format!("required fragment present in ruleset: {f}"))
+                            .with_evidence(
+                                Evidence::rule(f)
+                                    .with_snippet(format!("{family}: {}", hit.snippet())),
+                            )
+                    }
+                    None => {
+                        let mut finding = Finding::new(
+                            id,
+                            Overall::Warn,
+                            format!("required fragment not found in ruleset: {f}"),
+                        )
+                        .with_evidence(Evidence::rule(f))
+                        .with_values(
+                            format!("`{f}` on the traffic path"),
+                            "not found in any built-in chain or chain reachable from one",
+                        );
+                        // `-A <chain> ...` fragments are complete rules
+                        if let Some(rule) = f.strip_prefix("-A ") {
+                            finding = finding.with_remediation(format!("iptables -I {rule}"));
+                        }
+                        finding
+                    }
+                });
+            }
+        }
+
+        if let Some(frags) = checks.and_then(|c| c.forbidden_fragments.as_ref()) {
+            for f in frags {
+                let hits = hits_where(&families, |r| r.find_fragment(f));
+                let id = format!("forbidden-fragment:{f}");
+                findings.push(if hits.is_empty() {
+                    Finding::pass(id, format!("forbidden fragment not in ruleset: {f}"))
+                } else {
+                    with_hits(
+                        Finding::new(
+                            id,
+                            Overall::Error,
+                            format!(
+                                "forbidden fragment present in {} ruleset: {f}",
+                                hit_families(&hits)
+                            ),
+                        ),
+                        f,
+                        format!("no `{f}` on the traffic path"),
+                        &hits,
+                    )
+                });
+            }
+        }
+
+        if let Some(ports) = checks.and_then(|c| c.required_ports.as_ref()) {
+            for p in ports {
+                let Some((port, proto)) = parse_port_spec(p) else {
+                    findings.push(invalid_port(p));
+                    continue;
+                };
+                let id = format!("missing-port:{p}");
+                let hits = hits_where(&families, |r| r.accepting_rule(proto, port));
+                findings.push(if hits.is_empty() {
+                    let fix: Vec<String> = families
+                        .iter()
+                        .map(|(family, _)| {
+                            format!(
+                                "{} -I INPUT -p {proto} --dport {port} -j ACCEPT",
+                                program(family)
+                            )
+                        })
+                        .collect();
+                    Finding::new(
+                        id,
+                        Overall::Warn,
+                        format!("no rule on the INPUT path accepts required port: {p}"),
+                    )
+                    .with_evidence(Evidence::rule(p))
+                    .with_values(
+                        format!("{p} accepted on the INPUT path"),
+                        "no ACCEPT rule matches",
+                    )
+                    .with_remediation(fix.join(" && "))
+                } else {
+                    Finding::pass(id, format!("required port accepted on the INPUT path: {p}"))
+                        .with_evidence(Evidence::rule(p).with_snippet(hit_lines(&hits, "\n")))
+                });
+            }
+        }
+
+        if let Some(ports) = checks.and_then(|c| c.forbidden_ports.as_ref()) {
+            for p in ports {
+                let Some((port, proto)) = parse_port_spec(p) else {
+                    findings.push(invalid_port(p));
+                    continue;
+                };
+                let hits = hits_where(&families, |r| r.accepting_rule(proto, port));
+                let id = format!("forbidden-port:{p}");
+                findings.push(if hits.is_empty() {
+                    Finding::pass(
+                        id,
+                        format!("forbidden port not accepted on the INPUT path: {p}"),
+                    )
+                } else {
+                    with_hits(
+                        Finding::new(
+                            id,
+                            Overall::Error,
+                            format!(
+                                "forbidden port accepted on the {} INPUT path: {p}",
+                                hit_families(&hits)
+                            ),
+                        ),
+                        p,
+                        format!("{p} not accepted"),
+                        &hits,
+                    )
+                });
+            }
+        }
+
+        if let Some(ipt) = checks.and_then(|c| c.iptables.as_ref()) {
+            validate_structure(&families, ipt, &mut findings);
+        }
+
+        findings.extend(skip_rules(checks, SERVICE_RULES, NO_SERVICES));
+        Ok(findings)
+    }
+
+    fn normalize(&self, snap: &BackendStatus) -> String {
+        let Some(rulesets) = &snap.iptables else {
+            return snap.raw.trim().to_string();
+        };
+        let mut out = String::new();
+        for (family, r) in rulesets.families() {
+            out.push_str(&format!("# {family}\n{r}"));
+        }
+        out
+    }
+}
+
+const NO_SERVICES: &str = "services are checked by the firewalld and ufw backends";
+
+/// Skipped results for the generic rules this backend evaluates.
+fn skip_evaluated(checks: Option<&Checks>, why: &str) -> Vec<Finding> {
+    skip_rules(checks, &[FRAGMENT_RULES, PORT_RULES].concat(), why)
+}
+
+fn validate_structure(
+    families: &[(&'static str, &Ruleset)],
+    checks: &IptChecks,
+    findings: &mut Vec<Finding>,
+) {
+    let selected = |family: &Option<String>| {
+        families
+            .iter()
+            .filter(|(f, _)| family.as_deref().is_none_or(|want| want == *f))
+            .copied()
+            .collect::<Vec<_>>()
+    };
+
+    for c in checks.chains.iter().flatten() {
+        let table = c.table.as_deref().unwrap_or("filter");
+        for (family, ruleset) in selected(&c.family) {
+            let label = format!("{family}/{table}/{}", c.chain);
+            let ipt = format!("{} -t {table}", program(family));
+            let found = ruleset
+                .table(table)
+                .and_then(|t| t.chain(&c.chain).map(|chain| (t, chain)));
+            let Some((t, chain)) = found else {
+                findings.push(
+                    Finding::new(
+                        format!("ipt-chain:{label}"),
+                        Overall::Warn,
+                        format!("required chain not found in ruleset: {label}"),
+                    )
+                    .with_evidence(Evidence::rule(label.clone()))
+                    .with_values(format!("chain {}", c.chain), "no such chain")
+                    .with_remediation(format!("{ipt} -N {}", c.chain)),
+                );
+                if c.policy.is_some() {
+                    findings.push(Finding::skipped(
+                        format!("ipt-chain-policy:{label}"),
+                        format!("chain {label} not found"),
+                    ));
+                }
+                continue;
+            };
+            findings.push(Finding::pass(
+                format!("ipt-chain:{label}"),
+                format!("required chain present in ruleset: {label}"),
+            ));
+            if let Some(policy) = &c.policy {
+                let actual = chain.policy.as_deref().unwrap_or("none");
+                if !actual.eq_ignore_ascii_case(policy) {
+                    findings.push(
+                        Finding::new(
+                            format!("ipt-chain-policy:{label}"),
+                            Overall::Warn,
+                            format!("chain {label} has policy {actual}, expected {policy}"),
+                        )
+                        .with_evidence(
+                            Evidence::rule(label.clone())
+                                .with_snippet(Hit::new(t, chain, None).snippet()),
+                        )
+                        .with_values(format!("policy {policy}"), format!("policy {actual}"))
+                        .with_remediation(format!(
+                            "{ipt} -P {} {}",
+                            c.chain,
+                            policy.to_uppercase()
+                        )),
+                    );
+                } else {
+                    findings.push(Finding::pass(
+                        format!("ipt-chain-policy:{label}"),
+                        format!("chain {label} has policy {policy}"),
+                    ));
+                }
+            }
+        }
+    }
+
+    for r in checks.rules.iter().flatten() {
+        let table = r.table.as_deref().unwrap_or("filter");
+        for (family, ruleset) in selected(&r.family) {
+            let label = rule_label(family, r);
+            let ipt = format!("{} -t {table}", program(family));
+            let insert = format!("{ipt} -I {} {}", r.chain, rule_args(r));
+            let found = ruleset
+                .table(table)
+                .and_then(|t| t.chain(&r.chain).map(|chain| (t, chain)));
+            let Some((t, chain)) = found else {
+                findings.push(
+                    Finding::new(
+                        format!("ipt-rule:{label}"),
+                        Overall::Warn,
+                        format!(
+                            "chain {family}/{table}/{} not found for rule check",
+                            r.chain
+                        ),
+                    )
+                    .with_evidence(Evidence::rule(rule_desc(r)))
+                    .with_values(
+                        format!("{} in chain {family}/{table}/{}", rule_desc(r), r.chain),
+                        format!("chain {family}/{table}/{} not found", r.chain),
+                    )
+                    .with_remediation(format!("{ipt} -N {} && {insert}", r.chain)),
+                );
+                if r.before_drop.unwrap_or(false) {
+                    findings.push(Finding::skipped(
+                        format!("ipt-rule-order:{label}"),
+                        format!("chain {family}/{table}/{} not found", r.chain),
+                    ));
+                }
+                continue;
+            };
+
+            let Some(pos) = find_rule(chain, r) else {
+                findings.push(
+                    Finding::new(
+                        format!("ipt-rule:{label}"),
+                        Overall::Warn,
+                        format!(
+                            "no rule matching {} in {family}/{table}/{}",
+                            rule_desc(r),
+                            r.chain
+                        ),
+                    )
+                    .with_evidence(Evidence::rule(rule_desc(r)))
+                    .with_values(
+                        format!("{} in chain {family}/{table}/{}", rule_desc(r), r.chain),
+                        format!("no matching rule among {} rules", chain.rules.len()),
+                    )
+                    .with_remediation(insert),
+                );
+                if r.before_drop.unwrap_or(false) {
+                    findings.push(Finding::skipped(
+                        format!("ipt-rule-order:{label}"),
+                        format!("rule {} not found", rule_desc(r)),
+                    ));
+                }
+                continue;
+            };
+            let hit = Hit::new(t, chain, Some(&chain.rules[pos]));
+            findings.push(
+                Finding::pass(
+                    format!("ipt-rule:{label}"),
+                    format!(
+                        "rule {} present in {family}/{table}/{}",
+                        rule_desc(r),
+                        r.chain
+                    ),
+                )
+                .with_evidence(Evidence::rule(rule_desc(r)).with_snippet(hit.snippet())),
+            );
+
+            if r.before_drop.unwrap_or(false) {
+                let shadow = chain.rules[..pos].iter().find(|rule| {
+                    matches!(rule.target(), Some("DROP" | "REJECT"))
+                        && rule.covers_port(&r.protocol, r.dport)
+                });
+                if let Some(shadow) = shadow {
+                    let shadow = Hit::new(t, chain, Some(shadow));
+                    let matched = &chain.rules[pos];
+                    findings.push(
+                        Finding::new(
+                            format!("ipt-rule-order:{label}"),
+                            Overall::Warn,
+                            format!(
+                                "rule {} in {family}/{table}/{} is preceded by a DROP/REJECT that matches the same traffic",
+                                rule_desc(r),
+                                r.chain
+                            ),
+                        )
+                        .with_evidence(
+                            Evidence::rule(rule_desc(r))
+                                .with_snippet(format!("{}\n{}", shadow.snippet(), hit.snippet())),
+                        )
+                        .with_values(
+                            format!("{} before any matching DROP/REJECT", rule_desc(r)),
+                            format!("preceded by: {}", shadow.snippet()),
+                        )
+                        // move the matching rule to the top of the chain
+                        .with_remediation(format!(
+                            "{ipt} -D {chain} {matched} && {ipt} -I {chain} {matched}",
+                            chain = r.chain
+                        )),
+                    );
+                } else {
+                    findings.push(Finding::pass(
+                        format!("ipt-rule-order:{label}"),
+                        format!(
+                            "rule {} in {family}/{table}/{} is not preceded by a DROP/REJECT",
+                            rule_desc(r),
+                            r.chain
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    for r in checks.forbidden_rules.iter().flatten() {
+        let table = r.table.as_deref().unwrap_or("filter");
+        for (family, ruleset) in selected(&r.family) {
+            let hit = ruleset.table(table).and_then(|t| {
+                let chain = t.chain(&r.chain)?;
+                find_rule(chain, r).map(|pos| Hit::new(t, chain, Some(&chain.rules[pos])))
+            });
+            let id = format!("ipt-forbidden-rule:{}", rule_label(family, r));
+            findings.push(match hit {
+                Some(hit) => with_hits(
+                    Finding::new(
+                        id,
+                        Overall::Error,
+                        format!(
+                            "forbidden rule present in {family}/{table}/{}: {}",
+                            r.chain,
+                            rule_desc(r)
+                        ),
+                    ),
+                    &rule_desc(r),
+                    format!("no {} in chain {family}/{table}/{}", rule_desc(r), r.chain),
+                    &[(family, hit)],
+                ),
+                None => Finding::pass(
+                    id,
+                    format!(
+                        "forbidden rule not in {family}/{table}/{}: {}",
+                        r.chain,
+                        rule_desc(r)
+                    ),
+                ),
+            });
+        }
+    }
+}
+
+/// Rule specification for `-I`: `-p tcp --dport 22 -j ACCEPT`.
+fn rule_args(r: &IptRuleCheck) -> String {
+    format!(
+        "-p {} --dport {} -j {}",
+        r.protocol,
+        r.dport,
+        r.target.as_deref().unwrap_or("ACCEPT")
+    )
+}
+
+fn rule_desc(r: &IptRuleCheck) -> String {
+    format!(
+        "{} dport {} {}",
+        r.protocol,
+        r.dport,
+        r.target.as_deref().unwrap_or("ACCEPT")
+    )
+}
+
+fn rule_label(family: &str, r: &IptRuleCheck) -> String {
+    format!(
+        "{family}/{}/{}/{}",
+        r.table.as_deref().unwrap_or("filter"),
+        r.chain,
+        rule_desc(r)
+    )
+}
+
+/// Position of the first rule in `chain` matching the check's port and target.
+fn find_rule(chain: &ruleset::Chain, r: &IptRuleCheck) -> Option<usize> {
+    let target = r.target.as_deref().unwrap_or("ACCEPT");
+    chain
+        .rules
+        .iter()
+        .position(|rule| rule.matches_port(&r.protocol, r.dport) && rule.target() == Some(target))
+}
+
+/// First matching line per family.
+fn hits_where<'a>(
+    families: &[(&'static str, &'a Ruleset)],
+    find: impl Fn(&'a Ruleset) -> Option<Hit<'a>>,
+) -> Vec<(&'static str, Hit<'a>)> {
+    families
+        .iter()
+        .filter_map(|(f, r)| find(r).map(|hit| (*f, hit)))
+        .collect()
+}
+
+fn hit_families(hits: &[(&'static str, Hit<'_>)]) -> String {
+    hits.iter().map(|(f, _)| *f).collect::<Vec<_>>().join("/")
+}
+
+/// `<family>: <line>` per hit.
+fn hit_lines(hits: &[(&'static str, Hit<'_>)], sep: &str) -> String {
+    hits.iter()
+        .map(|(f, hit)| format!("{f}: {}", hit.snippet()))
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
+/// Evidence, values and delete commands for lines that must not exist.
+fn with_hits(
+    finding: Finding,
+    rule: &str,
+    expected: String,
+    hits: &[(&'static str, Hit<'_>)],
+) -> Finding {
+    let fix: Vec<String> = hits
+        .iter()
+        .filter_map(|(f, hit)| Some(format!("{} {}", program(f), hit.fix_args()?)))
+        .collect();
+    let finding = finding
+        .with_evidence(Evidence::rule(rule).with_snippet(hit_lines(hits, "\n")))
+        .with_values(expected, hit_lines(hits, "; "));
+    if fix.is_empty() {
+        finding
+    } else {
+        finding.with_remediation(fix.join(" && "))
+    }
+}
+
+/// `iptables` for ipv4, `ip6tables` for ipv6.
+fn program(family: &str) -> &'static str {
+    if family == "ipv6" {
+        "ip6tables"
+    } else {
+        "iptables"
+    }
+}
+
+fn invalid_port(p: &str) -> Finding {
+    Finding::new(
+        format!("invalid-port:{p}"),
+        Overall::Warn,
+        format!("port is not a <port>/<proto> spec: {p}"),
+    )
+}
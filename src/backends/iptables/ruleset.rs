@@ -0,0 +1,621 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt;
+
+/// Typed view of `iptables-save` / `ip6tables-save` output.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Ruleset {
+    pub tables: Vec<Table>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Table {
+    pub name: String,
+    pub chains: Vec<Chain>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Chain {
+    pub name: String,
+    /// `ACCEPT`/`DROP` for built-in chains, `None` for user-defined ones.
+    pub policy: Option<String>,
+    pub rules: Vec<Rule>,
+}
+
+/// One `-A <chain> ...` line; `args` are the tokens after the chain name.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Rule {
+    pub args: Vec<String>,
+}
+
+/// Match options that only select on protocol/destination port.
+const PORT_OPTS: &[&str] = &[
+    "-p",
+    "--protocol",
+    "-m",
+    "--match",
+    "--dport",
+    "--destination-port",
+    "--dports",
+    "--destination-ports",
+];
+
+impl Ruleset {
+    pub fn parse(raw: &str) -> Result<Ruleset> {
+        let mut ruleset = Ruleset::default();
+        let mut current: Option<Table> = None;
+
+        for (n, line) in raw.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('*') {
+                if let Some(t) = current.take() {
+                    ruleset.tables.push(t);
+                }
+                current = Some(Table {
+                    name: name.trim().to_string(),
+                    chains: Vec::new(),
+                });
+                continue;
+            }
+            if line == "COMMIT" {
+                if let Some(t) = current.take() {
+                    ruleset.tables.push(t);
+                }
+                continue;
+            }
+
+            let Some(table) = current.as_mut() else {
+                bail!("line {}: rule outside of a table: {line}", n + 1);
+            };
+            if let Some(decl) = line.strip_prefix(':') {
+                let mut parts = decl.split_whitespace();
+                let name = parts.next().unwrap_or_default().to_string();
+                let policy = parts.next().filter(|p| *p != "-").map(String::from);
+                table.chains.push(Chain {
+                    name,
+                    policy,
+                    rules: Vec::new(),
+                });
+            } else if let Some(rest) = line.strip_prefix("-A ") {
+                let mut args = split_args(rest);
+                if args.is_empty() {
+                    bail!("line {}: -A without chain", n + 1);
+                }
+                let chain_name = args.remove(0);
+                match table.chains.iter_mut().find(|c| c.name == chain_name) {
+                    Some(chain) => chain.rules.push(Rule { args }),
+                    None => bail!("line {}: rule for undeclared chain {chain_name}", n + 1),
+                }
+            }
+        }
+
+        if let Some(t) = current.take() {
+            ruleset.tables.push(t);
+        }
+        Ok(ruleset)
+    }
+
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables.iter().find(|t| t.name == name)
+    }
+
+    /// True if `fragment` appears as a contiguous token sequence in a chain
+    /// policy line (`:INPUT DROP`) or rule line (`-A INPUT ...`) reachable from
+    /// a built-in chain. Comments and counters are ignored.
+    pub fn contains_fragment(&self, fragment: &str) -> bool {
+        self.find_fragment(fragment).is_some()
+    }
+
+    /// First policy or rule line containing `fragment` (see
+    /// [`Ruleset::contains_fragment`]).
+    pub fn find_fragment(&self, fragment: &str) -> Option<Hit<'_>> {
+        let needle = split_args(fragment);
+        if needle.is_empty() {
+            return None;
+        }
+        self.tables.iter().find_map(|t| {
+            t.reachable_chains().into_iter().find_map(|c| {
+                let policy = [
+                    format!(":{}", c.name),
+                    c.policy.clone().unwrap_or("-".into()),
+                ];
+                if contains_window(&policy, &needle) {
+                    return Some(Hit::new(t, c, None));
+                }
+                c.rules
+                    .iter()
+                    .find(|r| {
+                        let mut line = vec!["-A".to_string(), c.name.clone()];
+                        line.extend(r.args_without_comment());
+                        contains_window(&line, &needle)
+                    })
+                    .map(|r| Hit::new(t, c, Some(r)))
+            })
+        })
+    }
+
+    /// True if `proto` traffic to `port` is accepted on the filter/INPUT path.
+    pub fn accepts_port(&self, proto: &str, port: u16) -> bool {
+        self.accepting_rule(proto, port).is_some()
+    }
+
+    /// First rule on the filter/INPUT path accepting `proto` traffic to
+    /// `port`, by port or by accepting every port. Failing that, the
+    /// `:INPUT ACCEPT` policy line when no rule drops or rejects the port.
+    pub fn accepting_rule(&self, proto: &str, port: u16) -> Option<Hit<'_>> {
+        let t = self.table("filter")?;
+        let chains = t.chains_from("INPUT");
+        let rule = chains.iter().find_map(|c| {
+            c.rules
+                .iter()
+                .find(|r| r.accepts_port(proto, port))
+                .map(|r| Hit::new(t, c, Some(r)))
+        });
+        rule.or_else(|| {
+            let input = t.chain("INPUT")?;
+            let dropped = chains.iter().flat_map(|c| &c.rules).any(|r| {
+                matches!(r.target(), Some("DROP" | "REJECT")) && r.covers_port(proto, port)
+            });
+            (input.policy.as_deref() == Some("ACCEPT") && !dropped)
+                .then(|| Hit::new(t, input, None))
+        })
+    }
+}
+
+/// Where a lookup matched: a rule, or the chain's policy line when `rule` is
+/// `None`.
+#[derive(Clone, Copy, Debug)]
+pub struct Hit<'a> {
+    pub table: &'a Table,
+    pub chain: &'a Chain,
+    pub rule: Option<&'a Rule>,
+}
+
+impl<'a> Hit<'a> {
+    pub fn new(table: &'a Table, chain: &'a Chain, rule: Option<&'a Rule>) -> Self {
+        Self { table, chain, rule }
+    }
+
+    /// The matched line as `iptables-save` prints it, prefixed with the table:
+    /// `*filter -A INPUT -p tcp --dport 23 -j ACCEPT`.
+    pub fn snippet(&self) -> String {
+        let line = match self.rule {
+            Some(r) => format!("-A {} {r}", self.chain.name),
+            None => format!(
+                ":{} {}",
+                self.chain.name,
+                self.chain.policy.as_deref().unwrap_or("-")
+            ),
+        };
+        format!("*{} {line}", self.table.name)
+    }
+
+    /// Arguments deleting the matched rule (`-t filter -D INPUT ...`); `None`
+    /// for policy lines.
+    pub fn delete_args(&self) -> Option<String> {
+        let rule = self.rule?;
+        Some(format!(
+            "-t {} -D {} {rule}",
+            self.table.name, self.chain.name
+        ))
+    }
+
+    /// [`Hit::delete_args`], or `-t filter -P INPUT DROP` for an `ACCEPT`
+    /// policy line.
+    pub fn fix_args(&self) -> Option<String> {
+        self.delete_args().or_else(|| {
+            (self.chain.policy.as_deref() == Some("ACCEPT"))
+                .then(|| format!("-t {} -P {} DROP", self.table.name, self.chain.name))
+        })
+    }
+}
+
+impl Table {
+    pub fn chain(&self, name: &str) -> Option<&Chain> {
+        self.chains.iter().find(|c| c.name == name)
+    }
+
+    /// Built-in chains plus every user chain reachable from them.
+    pub fn reachable_chains(&self) -> Vec<&Chain> {
+        self.reachable_from(self.chains.iter().filter(|c| c.is_builtin()).collect())
+    }
+
+    /// `root` plus the user chains it jumps to (transitively).
+    pub fn chains_from(&self, root: &str) -> Vec<&Chain> {
+        self.reachable_from(self.chain(root).into_iter().collect())
+    }
+
+    fn reachable_from<'a>(&'a self, mut queue: VecDeque<&'a Chain>) -> Vec<&'a Chain> {
+        let mut seen = BTreeSet::new();
+        let mut out = Vec::new();
+        while let Some(chain) = queue.pop_front() {
+            if !seen.insert(chain.name.as_str()) {
+                continue;
+            }
+            out.push(chain);
+            for rule in &chain.rules {
+                if let Some(next) = rule.target().and_then(|t| self.chain(t)) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        out
+    }
+}
+
+impl Chain {
+    pub fn is_builtin(&self) -> bool {
+        self.policy.is_some()
+    }
+}
+
+impl Rule {
+    /// Jump (`-j`) or goto (`-g`) target.
+    pub fn target(&self) -> Option<&str> {
+        self.opt(&["-j", "--jump", "-g", "--goto"])
+    }
+
+    pub fn protocol(&self) -> Option<&str> {
+        self.opt(&["-p", "--protocol"])
+    }
+
+    /// True if the rule matches `proto` traffic to `port` via `--dport`
+    /// (single port or `lo:hi` range) or multiport `--dports`.
+    pub fn matches_port(&self, proto: &str, port: u16) -> bool {
+        if self.protocol() != Some(proto) {
+            return false;
+        }
+        let single = self
+            .opt(&["--dport", "--destination-port"])
+            .is_some_and(|spec| spec_contains(spec, port));
+        let multi = self
+            .opt(&["--dports", "--destination-ports"])
+            .is_some_and(|list| list.split(',').any(|spec| spec_contains(spec, port)));
+        single || multi
+    }
+
+    /// True if the rule is an `ACCEPT` for `proto` traffic to `port`: it
+    /// matches the port, or lets it through without naming it (a bare
+    /// `-j ACCEPT`, `-i eth0 -j ACCEPT`, `-p tcp ! --dport 25 -j ACCEPT`).
+    /// Loopback, address and conntrack-restricted accepts do not count.
+    pub fn accepts_port(&self, proto: &str, port: u16) -> bool {
+        if self.target() != Some("ACCEPT") {
+            return false;
+        }
+        if self.matches_port(proto, port) {
+            return true;
+        }
+        let args = self.args_without_comment();
+        let end = args
+            .iter()
+            .position(|a| matches!(a.as_str(), "-j" | "--jump"))
+            .unwrap_or(args.len());
+        let mut negated = false;
+        let mut i = 0;
+        while i < end {
+            let (flag, value) = (args[i].as_str(), args.get(i + 1).map(String::as_str));
+            if flag == "!" {
+                negated = true;
+                i += 1;
+                continue;
+            }
+            let ok = match flag {
+                "-p" | "--protocol" => !negated && value == Some(proto),
+                "-i" | "--in-interface" => !negated && value != Some("lo"),
+                "-m" | "--match" => true,
+                "--dport" | "--destination-port" => {
+                    negated && value.is_some_and(|spec| !spec_contains(spec, port))
+                }
+                "--dports" | "--destination-ports" => {
+                    negated
+                        && value
+                            .is_some_and(|list| !list.split(',').any(|s| spec_contains(s, port)))
+                }
+                _ => false,
+            };
+            if !ok {
+                return false;
+            }
+            negated = false;
+            i += 2;
+        }
+        true
+    }
+
+    /// True if every packet of `proto` traffic to `port` hits this rule: it
+    /// has no match options at all, or only protocol/port matches.
+    pub fn covers_port(&self, proto: &str, port: u16) -> bool {
+        let flags: Vec<&str> = self
+            .match_args()
+            .iter()
+            .map(String::as_str)
+            .filter(|a| a.starts_with('-'))
+            .collect();
+        flags.is_empty()
+            || (flags.iter().all(|f| PORT_OPTS.contains(f)) && self.matches_port(proto, port))
+    }
+
+    /// Tokens before the target; target options such as `--reject-with` are excluded.
+    fn match_args(&self) -> &[String] {
+        let end = self
+            .args
+            .iter()
+            .position(|a| matches!(a.as_str(), "-j" | "--jump" | "-g" | "--goto"))
+            .unwrap_or(self.args.len());
+        &self.args[..end]
+    }
+
+    /// Value following the first of `flags`, unless negated with a preceding `!`.
+    fn opt(&self, flags: &[&str]) -> Option<&str> {
+        let i = self.args.iter().position(|a| flags.contains(&a.as_str()))?;
+        if i > 0 && self.args[i - 1] == "!" {
+            return None;
+        }
+        self.args.get(i + 1).map(String::as_str)
+    }
+
+    fn args_without_comment(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        let mut iter = self.args.iter().peekable();
+        while let Some(a) = iter.next() {
+            if a == "-m" && iter.peek().is_some_and(|n| *n == "comment") {
+                iter.next();
+                continue;
+            }
+            if a == "--comment" {
+                iter.next();
+                continue;
+            }
+            out.push(a.clone());
+        }
+        out
+    }
+}
+
+impl fmt::Display for Ruleset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for t in &self.tables {
+            writeln!(f, "*{}", t.name)?;
+            for c in &t.chains {
+                writeln!(f, ":{} {}", c.name, c.policy.as_deref().unwrap_or("-"))?;
+            }
+            for c in &t.chains {
+                for r in &c.rules {
+                    writeln!(f, "-A {} {r}", c.name)?;
+                }
+            }
+            writeln!(f, "COMMIT")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self
+            .args
+            .iter()
+            .map(|a| {
+                if a.is_empty() || a.contains(char::is_whitespace) {
+                    format!("\"{}\"", a.replace('"', "\\\""))
+                } else {
+                    a.clone()
+                }
+            })
+            .collect();
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// Split a rule line into arguments, honouring the double quotes and
+/// backslash escapes `iptables-save` uses for comments.
+fn split_args(s: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut cur = String::new();
+    let mut in_quotes = false;
+    let mut has_token = false;
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    cur.push(next);
+                    has_token = true;
+                }
+            }
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_token {
+                    out.push(std::mem::take(&mut cur));
+                    has_token = false;
+                }
+            }
+            c => {
+                cur.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if has_token {
+        out.push(cur);
+    }
+    out
+}
+
+/// `22` or `1000:2000` (iptables range syntax).
+fn spec_contains(spec: &str, port: u16) -> bool {
+    match spec.split_once(':') {
+        Some((lo, hi)) => {
+            let lo = if lo.is_empty() {
+                Ok(0)
+            } else {
+                lo.parse::<u16>()
+            };
+            let hi = if hi.is_empty() {
+                Ok(u16::MAX)
+            } else {
+                hi.parse::<u16>()
+            };
+            matches!((lo, hi), (Ok(lo), Ok(hi)) if (lo..=hi).contains(&port))
+        }
+        None => spec.parse::<u16>().ok() == Some(port),
+    }
+}
+
+fn contains_window(haystack: &[String], needle: &[String]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Ruleset {
+        let path = format!(
+            "{}/tests/fixtures/iptables/{name}",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        Ruleset::parse(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn names(chains: &[&Chain]) -> Vec<String> {
+        chains.iter().map(|c| c.name.clone()).collect()
+    }
+
+    #[test]
+    fn parses_multiple_tables_and_user_chains() {
+        let ruleset = fixture("ipv4.save");
+        let tables: Vec<&str> = ruleset.tables.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(tables, ["nat", "filter"]);
+        assert!(!ruleset
+            .table("nat")
+            .unwrap()
+            .chain("DOCKER")
+            .unwrap()
+            .is_builtin());
+
+        let filter = ruleset.table("filter").unwrap();
+        assert_eq!(
+            filter.chain("INPUT").unwrap().policy.as_deref(),
+            Some("DROP")
+        );
+        assert_eq!(filter.chain("services").unwrap().policy, None);
+        assert_eq!(
+            filter.chain("INPUT").unwrap().rules[4].target(),
+            Some("logdrop")
+        );
+        assert_eq!(
+            names(&filter.reachable_chains()),
+            ["INPUT", "FORWARD", "OUTPUT", "services", "logdrop"]
+        );
+
+        assert!(ruleset.accepts_port("tcp", 22));
+        assert!(ruleset.accepts_port("tcp", 443));
+        assert!(ruleset.accepts_port("tcp", 8080));
+        assert!(ruleset.accepts_port("udp", 161));
+        // `! --dport 25` accepts every other tcp port, 23 included; the
+        // explicit accept in a chain nothing jumps to is never reached
+        assert!(!ruleset.accepts_port("tcp", 25));
+        let telnet = ruleset.accepting_rule("tcp", 23).unwrap();
+        assert_eq!(
+            telnet.snippet(),
+            "*filter -A services -p tcp ! --dport 25 -j ACCEPT"
+        );
+        assert!(!ruleset.contains_fragment("--dport 23 -j ACCEPT"));
+        assert!(ruleset.contains_fragment(":INPUT DROP"));
+    }
+
+    #[test]
+    fn keeps_quoted_comments_as_one_argument() {
+        let ruleset = fixture("ipv4.save");
+        let filter = ruleset.table("filter").unwrap();
+        let ssh = &filter.chain("INPUT").unwrap().rules[2];
+        assert!(ssh.args.contains(&"ssh from \"anywhere\"".to_string()));
+        assert_eq!(
+            ssh.to_string(),
+            r#"-p tcp -m tcp --dport 22 -m comment --comment "ssh from \"anywhere\"" -j ACCEPT"#
+        );
+        assert!(filter.chain("logdrop").unwrap().rules[0]
+            .args
+            .contains(&"fw drop: ".to_string()));
+
+        // comments are ignored when matching fragments
+        let hit = ruleset
+            .find_fragment("-A INPUT -p tcp -m tcp --dport 22 -j ACCEPT")
+            .unwrap();
+        assert_eq!(
+            hit.delete_args().unwrap(),
+            format!("-t filter -D INPUT {ssh}")
+        );
+
+        // the rendered ruleset parses back to itself
+        let rendered = ruleset.to_string();
+        assert_eq!(Ruleset::parse(&rendered).unwrap().to_string(), rendered);
+    }
+
+    #[test]
+    fn parses_ip6tables_save_output() {
+        let ruleset = fixture("ipv6.save");
+        assert_eq!(ruleset.tables.len(), 1);
+        assert!(ruleset.accepts_port("tcp", 22));
+        assert!(ruleset.accepts_port("udp", 546));
+        // rejected, not accepted
+        assert!(!ruleset.accepts_port("tcp", 23));
+        assert_eq!(
+            ruleset.find_fragment("-s fe80::/10").unwrap().snippet(),
+            "*filter -A INPUT -s fe80::/10 -p udp -m udp --dport 546 -j ACCEPT"
+        );
+    }
+
+    #[test]
+    fn input_policy_accept_exposes_ports_nothing_drops() {
+        let ruleset = fixture("policy-accept.save");
+        let hit = ruleset.accepting_rule("tcp", 3389).unwrap();
+        assert!(hit.rule.is_none());
+        assert_eq!(hit.snippet(), "*filter :INPUT ACCEPT");
+        assert_eq!(hit.fix_args().unwrap(), "-t filter -P INPUT DROP");
+        assert!(ruleset.accepts_port("udp", 53));
+        // rejected before the policy applies
+        assert!(!ruleset.accepts_port("tcp", 23));
+    }
+
+    #[test]
+    fn port_less_accepts_expose_every_port() {
+        let ruleset = fixture("accept-all.save");
+        let hit = ruleset.accepting_rule("tcp", 3389).unwrap();
+        assert_eq!(
+            hit.snippet(),
+            "*filter -A INPUT -i eth1 -m comment --comment lan -j ACCEPT"
+        );
+        assert!(ruleset.accepts_port("udp", 161));
+
+        // loopback, conntrack and source-restricted accepts alone do not count
+        let mut restricted = ruleset.clone();
+        restricted.tables[0].chains[0].rules.pop();
+        assert!(!restricted.accepts_port("tcp", 3389));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let err = |raw: &str| format!("{:#}", Ruleset::parse(raw).unwrap_err());
+        assert_eq!(
+            err("-A INPUT -j ACCEPT"),
+            "line 1: rule outside of a table: -A INPUT -j ACCEPT"
+        );
+        assert_eq!(
+            err("*filter\n:INPUT ACCEPT [0:0]\n-A FORWARD -j DROP\nCOMMIT"),
+            "line 3: rule for undeclared chain FORWARD"
+        );
+        // a table cut off before COMMIT is still kept
+        let truncated = Ruleset::parse("*filter\n:INPUT DROP [0:0]\n").unwrap();
+        assert!(truncated.table("filter").is_some());
+    }
+}
@@ -1,34 +1,171 @@
-use crate::{config::Policy, status::Finding};
+use crate::{
+    cmd::run_timeout,
+    config::{Checks, Policy},
+    status::Finding,
+};
 use anyhow::Result;
+use std::collections::BTreeMap;
+use tracing::debug;
 
+pub mod file;
 pub mod firewalld;
+pub mod iptables;
 pub mod nftables;
+pub mod plugin;
+pub mod ufw;
 
 #[derive(Clone, Debug)]
 pub struct BackendStatus {
-    pub backend_name: &'static str,
+    pub backend_name: String,
     pub active: bool,
     pub facts: Vec<(String, String)>,
     pub raw: String,
+    /// Parsed nftables ruleset (nftables backend only).
+    pub ruleset: Option<nftables::Ruleset>,
+    /// Parsed iptables/ip6tables rulesets (iptables backend only).
+    pub iptables: Option<iptables::Rulesets>,
 }
 
 #[async_trait::async_trait]
 pub trait Backend: Send + Sync {
-    fn name(&self) -> &'static str;
+    fn name(&self) -> &str;
 
     /// Lightweight detection: is this backend usable on this machine right now?
     async fn detect(&self) -> Result<bool>;
 
+    /// Commands (argv) whose output the snapshot is built from; recorded by
+    /// `snapshot export`.
+    fn snapshot_commands(&self) -> &'static [&'static [&'static str]];
+
+    /// Build the snapshot from captured command outputs. A command missing
+    /// from `outputs` could not be run.
+    fn snapshot_from(&self, outputs: &Outputs) -> Result<BackendStatus>;
+
     /// Gather current state for status output / diff.
-    async fn snapshot(&self) -> Result<BackendStatus>;
+    async fn snapshot(&self) -> Result<BackendStatus> {
+        let outputs = Outputs::capture(self.snapshot_commands()).await;
+        self.snapshot_from(&outputs)
+    }
 
     /// Validate policy against current snapshot (MVP checks ok; evolve later).
     async fn validate(&self, policy: &Policy, snap: &BackendStatus) -> Result<Vec<Finding>>;
+
+    /// False for backends replaying recorded outputs; their reports are kept
+    /// out of the check history.
+    fn is_live(&self) -> bool {
+        true
+    }
+
+    /// Long-running command that prints a line whenever the firewall state
+    /// changes; used by `watch` to re-check immediately.
+    fn monitor_command(&self) -> Option<(&'static str, &'static [&'static str])> {
+        None
+    }
+
+    /// Stable textual form of the snapshot, used for baseline diffs.
+    fn normalize(&self, snap: &BackendStatus) -> String {
+        let mut out = String::new();
+        for line in snap.raw.lines().map(str::trim_end) {
+            if !line.is_empty() {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+/// Finding kinds of the generic `[checks]` rule lists, for [`skip_rules`].
+pub const SERVICE_RULES: &[&str] = &["missing-service", "forbidden-service"];
+pub const PORT_RULES: &[&str] = &["missing-port", "forbidden-port"];
+pub const FRAGMENT_RULES: &[&str] = &["missing-fragment", "forbidden-fragment"];
+
+/// Skipped results for every rule of the generic `[checks]` lists with the
+/// given finding kinds: lists a backend cannot evaluate, or all of them when
+/// the firewall state could not be read.
+pub fn skip_rules(checks: Option<&Checks>, kinds: &[&str], why: &str) -> Vec<Finding> {
+    let Some(c) = checks else {
+        return Vec::new();
+    };
+    let mut skipped = Vec::new();
+    for &kind in kinds {
+        let list = match kind {
+            "missing-service" => &c.required_services,
+            "forbidden-service" => &c.forbidden_services,
+            "missing-port" => &c.required_ports,
+            "forbidden-port" => &c.forbidden_ports,
+            "missing-fragment" => &c.required_fragments,
+            "forbidden-fragment" => &c.forbidden_fragments,
+            _ => continue,
+        };
+        for item in list.iter().flatten() {
+            skipped.push(Finding::skipped(format!("{kind}:{item}"), why));
+        }
+    }
+    skipped
 }
 
-pub fn all_backends() -> Vec<Box<dyn Backend>> {
+/// Built-in backends in detection order. Plugins are never auto-detected;
+/// they only run when named with `--backend` or in the policy.
+pub fn builtin_backends() -> Vec<Box<dyn Backend>> {
     vec![
         Box::new(firewalld::FirewalldBackend),
+        // ufw drives iptables/nftables itself, so it must be detected first
+        Box::new(ufw::UfwBackend),
         Box::new(nftables::NftablesBackend),
+        Box::new(iptables::IptablesBackend),
     ]
 }
+
+/// Exit code and output of one snapshot command.
+#[derive(Clone, Debug, Default)]
+pub struct CommandOutput {
+    pub code: i32,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Snapshot command outputs keyed by argv.
+#[derive(Clone, Debug, Default)]
+pub struct Outputs(BTreeMap<String, CommandOutput>);
+
+impl Outputs {
+    /// Run every command; ones that fail to spawn or time out are left out.
+    pub async fn capture(commands: &[&[&str]]) -> Outputs {
+        let mut outputs = Outputs::default();
+        for argv in commands {
+            let Some((program, args)) = argv.split_first() else {
+                continue;
+            };
+            match run_timeout(program, args, 3000).await {
+                Ok((code, stdout, stderr)) => outputs.insert(
+                    argv,
+                    CommandOutput {
+                        code,
+                        stdout,
+                        stderr,
+                    },
+                ),
+                Err(err) => {
+                    debug!(error = ?err, command = argv.join(" "), "snapshot command failed")
+                }
+            }
+        }
+        outputs
+    }
+
+    pub fn get(&self, argv: &[&str]) -> Option<&CommandOutput> {
+        self.0.get(&argv.join(" "))
+    }
+
+    pub fn insert(&mut self, argv: &[&str], output: CommandOutput) {
+        self.0.insert(argv.join(" "), output);
+    }
+
+    /// Stdout of a command that ran and exited 0.
+    pub fn stdout_ok(&self, argv: &[&str]) -> Option<&str> {
+        self.get(argv)
+            .filter(|o| o.code == 0)
+            .map(|o| o.stdout.as_str())
+    }
+}
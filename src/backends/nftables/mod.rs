@@ -0,0 +1,865 @@
+use anyhow::Result;
+use tracing::debug;
+
+use crate::{
+    backends::{skip_rules, Backend, BackendStatus, Outputs, FRAGMENT_RULES, SERVICE_RULES},
+    cmd::run_timeout,
+    config::{parse_port_spec, Checks, NftChainCheck, NftChecks, NftRuleCheck, Policy},
+    status::{Evidence, Finding, Overall},
+};
+
+pub mod ruleset;
+
+pub use ruleset::{Hit, Ruleset, Verdict};
+
+pub struct NftablesBackend;
+
+const LIST_RULESET: &[&str] = &["nft", "-j", "list", "ruleset"];
+
+#[async_trait::async_trait]
+impl Backend for NftablesBackend {
+    fn name(&self) -> &str {
+        "nftables"
+    }
+
+    async fn detect(&self) -> Result<bool> {
+        match run_timeout("nft", &["list", "ruleset"], 2000).await {
+            Ok((code, _out, _err)) => Ok(code == 0),
+            Err(err) => {
+                debug!(error = ?err, "nftables detection failed");
+                Ok(false)
+            }
+        }
+    }
+
+    fn snapshot_commands(&self) -> &'static [&'static [&'static str]] {
+        &[LIST_RULESET]
+    }
+
+    fn snapshot_from(&self, outputs: &Outputs) -> Result<BackendStatus> {
+        let output = outputs.get(LIST_RULESET);
+        let active = output.is_some_and(|o| o.code == 0);
+
+        let mut facts = vec![];
+        let ruleset = match outputs.stdout_ok(LIST_RULESET) {
+            Some(out) => match Ruleset::parse(out) {
+                Ok(r) => Some(r),
+                Err(err) => {
+                    debug!(error = ?err, "failed to parse nft JSON ruleset");
+                    facts.push(("parse_error".into(), format!("{err:#}")));
+                    None
+                }
+            },
+            None => None,
+        };
+        let raw = output
+            .map(|o| if active { &o.stdout } else { &o.stderr })
+            .cloned()
+            .unwrap_or_default();
+
+        Ok(BackendStatus {
+            backend_name: self.name().to_string(),
+            active,
+            facts,
+            raw,
+            ruleset,
+            iptables: None,
+        })
+    }
+
+    async fn validate(&self, policy: &Policy, snap: &BackendStatus) -> Result<Vec<Finding>> {
+        let mut findings = Vec::new();
+        let checks = policy.checks.as_ref();
+
+        let required = checks
+            .and_then(|c| c.require_firewall_active)
+            .unwrap_or(true);
+        if !snap.active {
+            if required {
+                let mut finding = Finding::new(
+                    "nftables-unavailable",
+                    Overall::Error,
+                    "unable to read nftables ruleset (nft -j list ruleset failed)",
+                )
+                .with_remediation("systemctl enable --now nftables");
+                if !snap.raw.trim().is_empty() {
+                    finding =
+                        finding.with_evidence(Evidence::default().with_snippet(snap.raw.trim()));
+                }
+                findings.push(finding);
+            }
+            findings.extend(skip_evaluated(checks, "nftables ruleset is unavailable"));
+            findings.extend(unsupported(checks));
+            return Ok(findings);
+        }
+        if required {
+            findings.push(Finding::pass(
+                "nftables-available",
+                "nftables ruleset is readable",
+            ));
+        }
+
+        let Some(ruleset) = snap.ruleset.as_ref() else {
+            findings.push(Finding::new(
+                "nftables-parse-failed",
+                Overall::Error,
+                "unable to parse nftables JSON ruleset (nft -j list ruleset)",
+            ));
+            findings.extend(skip_evaluated(
+                checks,
+                "nftables ruleset could not be parsed",
+            ));
+            findings.extend(unsupported(checks));
+            return Ok(findings);
+        };
+
+        if let Some(frags) = checks.and_then(|c| c.required_fragments.as_ref()) {
+            for f in frags {
+                let id = format!("missing-fragment:{f}");
+                findings.push(match ruleset.find_fragment(f) {
+                    Some(hit) => {
+                        Finding::pass(id, format!("required fragment present in ruleset: {f}"))
+                            .with_evidence(Evidence::rule(f).with_snippet(hit.snippet()))
+                    }
+                    None => {
+                        let mut finding = Finding::new(
+                            id,
+                            Overall::Warn,
+                            format!("required fragment not found in ruleset: {f}"),
+                        )
+                        .with_evidence(Evidence::rule(f))
+                        .with_values(
+                            format!("`{f}` on the traffic path"),
+                            "not found in any base chain or chain reachable from one",
+                        );
+                        if let Some((table, chain)) = input_chain(ruleset) {
+                            finding = finding.with_remediation(format!(
+                                "nft insert rule {} {} {f}",
+                                table.spec(),
+                                chain.name
+                            ));
+                        }
+                        finding
+                    }
+                });
+            }
+        }
+
+        if let Some(frags) = checks.and_then(|c| c.forbidden_fragments.as_ref()) {
+            for f in frags {
+                let id = format!("forbidden-fragment:{f}");
+                findings.push(match ruleset.find_fragment(f) {
+                    Some(hit) => {
+                        let mut finding = Finding::new(
+                            id,
+                            Overall::Error,
+                            format!("forbidden fragment present in ruleset: {f}"),
+                        )
+                        .with_evidence(Evidence::rule(f).with_snippet(hit.snippet()))
+                        .with_values(format!("no `{f}` on the traffic path"), hit.snippet());
+                        if let Some(fix) = hit.delete_command() {
+                            finding = finding.with_remediation(fix);
+                        }
+                        finding
+                    }
+                    None => Finding::pass(id, format!("forbidden fragment not in ruleset: {f}")),
+                });
+            }
+        }
+
+        if let Some(ports) = checks.and_then(|c| c.forbidden_ports.as_ref()) {
+            for p in ports {
+                let Some((port, proto)) = parse_port_spec(p) else {
+                    findings.push(Finding::new(
+                        format!("invalid-port:{p}"),
+                        Overall::Warn,
+                        format!("forbidden port is not a <port>/<proto> spec: {p}"),
+                    ));
+                    continue;
+                };
+                let id = format!("forbidden-port:{p}");
+                findings.push(match ruleset.accepting_rule(proto, port) {
+                    Some(hit) => {
+                        let finding = Finding::new(
+                            id,
+                            Overall::Error,
+                            format!("forbidden port accepted on the input path: {p}"),
+                        )
+                        .with_evidence(Evidence::rule(p).with_snippet(hit.snippet()))
+                        .with_values(format!("{p} not accepted"), hit.snippet());
+                        let fix = hit.delete_command().unwrap_or_else(|| {
+                            format!(
+                                "nft chain {} {} '{{ policy drop; }}'",
+                                hit.table.spec(),
+                                hit.chain.name
+                            )
+                        });
+                        finding.with_remediation(fix)
+                    }
+                    None => Finding::pass(
+                        id,
+                        format!("forbidden port not accepted on the input path: {p}"),
+                    ),
+                });
+            }
+        }
+
+        if let Some(nft) = checks.and_then(|c| c.nftables.as_ref()) {
+            validate_structure(ruleset, nft, &mut findings);
+        }
+
+        findings.extend(unsupported(checks));
+        Ok(findings)
+    }
+
+    fn monitor_command(&self) -> Option<(&'static str, &'static [&'static str])> {
+        Some(("nft", &["monitor", "ruleset"]))
+    }
+
+    fn normalize(&self, snap: &BackendStatus) -> String {
+        match &snap.ruleset {
+            Some(ruleset) => ruleset.to_string(),
+            None => snap.raw.trim().to_string(),
+        }
+    }
+}
+
+/// Skipped results for the generic rules this backend evaluates.
+fn skip_evaluated(checks: Option<&Checks>, why: &str) -> Vec<Finding> {
+    let mut skipped = skip_rules(checks, FRAGMENT_RULES, why);
+    skipped.extend(skip_rules(checks, &["forbidden-port"], why));
+    skipped
+}
+
+/// Skipped results for the generic rules nftables has no equivalent for.
+fn unsupported(checks: Option<&Checks>) -> Vec<Finding> {
+    let mut skipped = skip_rules(
+        checks,
+        SERVICE_RULES,
+        "services are checked by the firewalld and ufw backends",
+    );
+    skipped.extend(skip_rules(
+        checks,
+        &["missing-port"],
+        "required ports are not checked on nftables; use required_fragments or [[checks.nftables.rules]]",
+    ));
+    skipped
+}
+
+fn validate_structure(ruleset: &Ruleset, checks: &NftChecks, findings: &mut Vec<Finding>) {
+    for t in checks.tables.iter().flatten() {
+        let id = format!("nft-table:{t}");
+        findings.push(if lookup_table(ruleset, t).is_some() {
+            Finding::pass(id, format!("required table present in ruleset: {t}"))
+        } else {
+            Finding::new(
+                id,
+                Overall::Warn,
+                format!("required table not found in ruleset: {t}"),
+            )
+            .with_evidence(Evidence::rule(format!("table {t}")))
+            .with_values(format!("table {t}"), "no such table")
+            .with_remediation(format!("nft add table {t}"))
+        });
+    }
+
+    for c in checks.chains.iter().flatten() {
+        let label = match (&c.chain, &c.hook) {
+            (Some(name), _) => format!("{}/{name}", c.table),
+            (None, Some(hook)) => format!("{}/hook:{hook}", c.table),
+            (None, None) => {
+                findings.push(
+                    Finding::new(
+                        format!("nft-chain-invalid:{}", c.table),
+                        Overall::Warn,
+                        format!("chain check for {} needs `chain` or `hook`", c.table),
+                    )
+                    .with_remediation(
+                        "set `chain` or `hook` in the [[checks.nftables.chains]] entry",
+                    ),
+                );
+                continue;
+            }
+        };
+
+        let table = lookup_table(ruleset, &c.table);
+        let chain = table.and_then(|t| match (&c.chain, &c.hook) {
+            (Some(name), _) => t.chain(name),
+            (None, Some(hook)) => t.base_chain(hook),
+            (None, None) => None,
+        });
+        let Some(chain) = chain else {
+            let wanted = chain_decl(c);
+            findings.push(
+                Finding::new(
+                    format!("nft-chain:{label}"),
+                    Overall::Warn,
+                    format!("required chain not found in ruleset: {label}"),
+                )
+                .with_evidence(Evidence::rule(label.clone()))
+                .with_values(
+                    if wanted.is_empty() {
+                        format!("chain {label}")
+                    } else {
+                        format!("chain {label} {{ {wanted} }}")
+                    },
+                    if table.is_some() {
+                        "no such chain"
+                    } else {
+                        "no such table"
+                    },
+                )
+                .with_remediation(add_chain(c, table.is_none())),
+            );
+            for (kind, wanted) in [
+                ("nft-chain-hook", c.hook.is_some()),
+                ("nft-chain-priority", c.priority.is_some()),
+                ("nft-chain-policy", c.policy.is_some()),
+            ] {
+                if wanted {
+                    findings.push(Finding::skipped(
+                        format!("{kind}:{label}"),
+                        format!("chain {label} not found"),
+                    ));
+                }
+            }
+            continue;
+        };
+        findings.push(Finding::pass(
+            format!("nft-chain:{label}"),
+            format!("required chain present in ruleset: {label}"),
+        ));
+        let evidence = || {
+            Evidence::rule(label.clone()).with_snippet(format!(
+                "table {} chain {}: {}",
+                c.table,
+                chain.name,
+                chain.header()
+            ))
+        };
+        // hook and priority are fixed when a base chain is created
+        let recreate = || {
+            format!(
+                "recreate the chain: nft delete chain {} {} && {}",
+                c.table,
+                chain.name,
+                add_chain(c, false)
+            )
+        };
+
+        if let Some(hook) = &c.hook {
+            let actual = chain.hook.as_deref().unwrap_or("none");
+            if actual != hook {
+                findings.push(
+                    Finding::new(
+                        format!("nft-chain-hook:{label}"),
+                        Overall::Warn,
+                        format!("chain {label} has hook {actual}, expected {hook}"),
+                    )
+                    .with_evidence(evidence())
+                    .with_values(format!("hook {hook}"), format!("hook {actual}"))
+                    .with_remediation(recreate()),
+                );
+            } else {
+                findings.push(Finding::pass(
+                    format!("nft-chain-hook:{label}"),
+                    format!("chain {label} has hook {hook}"),
+                ));
+            }
+        }
+
+        if let Some(prio) = c.priority {
+            if chain.priority != Some(prio) {
+                let actual = chain
+                    .priority
+                    .map(|p| p.to_string())
+                    .unwrap_or_else(|| "none".into());
+                findings.push(
+                    Finding::new(
+                        format!("nft-chain-priority:{label}"),
+                        Overall::Warn,
+                        format!("chain {label} has priority {actual}, expected {prio}"),
+                    )
+                    .with_evidence(evidence())
+                    .with_values(format!("priority {prio}"), format!("priority {actual}"))
+                    .with_remediation(recreate()),
+                );
+            } else {
+                findings.push(Finding::pass(
+                    format!("nft-chain-priority:{label}"),
+                    format!("chain {label} has priority {prio}"),
+                ));
+            }
+        }
+
+        if let Some(policy) = &c.policy {
+            let actual = chain.policy.as_deref().unwrap_or("none");
+            if actual != policy {
+                findings.push(
+                    Finding::new(
+                        format!("nft-chain-policy:{label}"),
+                        Overall::Warn,
+                        format!("chain {label} has policy {actual}, expected {policy}"),
+                    )
+                    .with_evidence(evidence())
+                    .with_values(format!("policy {policy}"), format!("policy {actual}"))
+                    .with_remediation(format!(
+                        "nft chain {} {} '{{ policy {policy}; }}'",
+                        c.table, chain.name
+                    )),
+                );
+            } else {
+                findings.push(Finding::pass(
+                    format!("nft-chain-policy:{label}"),
+                    format!("chain {label} has policy {policy}"),
+                ));
+            }
+        }
+    }
+
+    for r in checks.rules.iter().flatten() {
+        let label = rule_label(r);
+        let insert = format!("nft insert rule {} {} {}", r.table, r.chain, rule_desc(r));
+        let table = lookup_table(ruleset, &r.table);
+        let chain = table.and_then(|t| t.chain(&r.chain));
+        let (Some(table), Some(chain)) = (table, chain) else {
+            findings.push(
+                Finding::new(
+                    format!("nft-rule:{label}"),
+                    Overall::Warn,
+                    format!("chain {}/{} not found for rule check", r.table, r.chain),
+                )
+                .with_evidence(Evidence::rule(rule_desc(r)))
+                .with_values(
+                    format!("{} in chain {}/{}", rule_desc(r), r.table, r.chain),
+                    format!("chain {}/{} not found", r.table, r.chain),
+                )
+                .with_remediation({
+                    let mut fix = vec![format!("nft add chain {} {}", r.table, r.chain), insert];
+                    if table.is_none() {
+                        fix.insert(0, format!("nft add table {}", r.table));
+                    }
+                    fix.join(" && ")
+                }),
+            );
+            if r.before_drop.unwrap_or(false) {
+                findings.push(Finding::skipped(
+                    format!("nft-rule-order:{label}"),
+                    format!("chain {}/{} not found", r.table, r.chain),
+                ));
+            }
+            continue;
+        };
+
+        let Some(pos) = find_rule(table, chain, r) else {
+            findings.push(
+                Finding::new(
+                    format!("nft-rule:{label}"),
+                    Overall::Warn,
+                    format!(
+                        "no rule matching {} in {}/{}",
+                        rule_desc(r),
+                        r.table,
+                        r.chain
+                    ),
+                )
+                .with_evidence(Evidence::rule(rule_desc(r)))
+                .with_values(
+                    format!("{} in chain {}/{}", rule_desc(r), r.table, r.chain),
+                    format!("no matching rule among {} rules", chain.rules.len()),
+                )
+                .with_remediation(insert),
+            );
+            if r.before_drop.unwrap_or(false) {
+                findings.push(Finding::skipped(
+                    format!("nft-rule-order:{label}"),
+                    format!("rule {} not found", rule_desc(r)),
+                ));
+            }
+            continue;
+        };
+        let hit = Hit::new(table, chain, Some(&chain.rules[pos]));
+        findings.push(
+            Finding::pass(
+                format!("nft-rule:{label}"),
+                format!("rule {} present in {}/{}", rule_desc(r), r.table, r.chain),
+            )
+            .with_evidence(Evidence::rule(rule_desc(r)).with_snippet(hit.snippet())),
+        );
+
+        if r.before_drop.unwrap_or(false) {
+            let shadow = chain.rules[..pos].iter().find(|rule| {
+                matches!(rule.verdict(), Some(Verdict::Drop | Verdict::Reject))
+                    && rule.covers_port(table, &r.protocol, r.dport)
+            });
+            if let Some(shadow) = shadow {
+                let shadow = Hit::new(table, chain, Some(shadow));
+                // move the matching rule to the top of the chain
+                let mut fix: Vec<String> = hit.delete_command().into_iter().collect();
+                fix.push(format!(
+                    "nft insert rule {} {} {}",
+                    r.table, r.chain, chain.rules[pos]
+                ));
+                findings.push(
+                    Finding::new(format!("nft-rule-order:{label}"), Overall::Warn, format!(
+                        "rule {} in {}/{} is preceded by a drop/reject that matches the same traffic",
+                        rule_desc(r),
+                        r.table,
+                        r.chain
+                    ))
+                    .with_evidence(
+                        Evidence::rule(rule_desc(r))
+                            .with_snippet(format!("{}\n{}", shadow.snippet(), hit.snippet())),
+                    )
+                    .with_values(
+                        format!("{} before any matching drop/reject", rule_desc(r)),
+                        format!("preceded by: {}", shadow.rule.map(ToString::to_string).unwrap_or_default()),
+                    )
+                    .with_remediation(fix.join(" && ")),
+                );
+            } else {
+                findings.push(Finding::pass(
+                    format!("nft-rule-order:{label}"),
+                    format!(
+                        "rule {} in {}/{} is not preceded by a drop/reject",
+                        rule_desc(r),
+                        r.table,
+                        r.chain
+                    ),
+                ));
+            }
+        }
+    }
+
+    for r in checks.forbidden_rules.iter().flatten() {
+        let id = format!("nft-forbidden-rule:{}", rule_label(r));
+        let hit = lookup_table(ruleset, &r.table).and_then(|t| {
+            let chain = t.chain(&r.chain)?;
+            find_rule(t, chain, r).map(|pos| Hit::new(t, chain, Some(&chain.rules[pos])))
+        });
+        findings.push(match hit {
+            Some(hit) => {
+                let mut finding = Finding::new(
+                    id,
+                    Overall::Error,
+                    format!(
+                        "forbidden rule present in {}/{}: {}",
+                        r.table,
+                        r.chain,
+                        rule_desc(r)
+                    ),
+                )
+                .with_evidence(Evidence::rule(rule_desc(r)).with_snippet(hit.snippet()))
+                .with_values(
+                    format!("no {} in chain {}/{}", rule_desc(r), r.table, r.chain),
+                    hit.snippet(),
+                );
+                if let Some(fix) = hit.delete_command() {
+                    finding = finding.with_remediation(fix);
+                }
+                finding
+            }
+            None => Finding::pass(
+                id,
+                format!(
+                    "forbidden rule not in {}/{}: {}",
+                    r.table,
+                    r.chain,
+                    rule_desc(r)
+                ),
+            ),
+        });
+    }
+}
+
+/// `type filter hook input priority 0; policy drop;` for a base chain check,
+/// empty for a regular chain.
+fn chain_decl(c: &NftChainCheck) -> String {
+    let mut parts = Vec::new();
+    if let Some(hook) = &c.hook {
+        parts.push(format!(
+            "type filter hook {hook} priority {};",
+            c.priority.unwrap_or(0)
+        ));
+    }
+    if let Some(policy) = &c.policy {
+        parts.push(format!("policy {policy};"));
+    }
+    parts.join(" ")
+}
+
+/// `nft add chain` for a chain check (named after the hook when the check
+/// only gives one), preceded by `nft add table` when the table is missing.
+fn add_chain(c: &NftChainCheck, with_table: bool) -> String {
+    let name = c.chain.as_deref().or(c.hook.as_deref()).unwrap_or_default();
+    let decl = chain_decl(c);
+    let mut cmd = format!("nft add chain {} {name}", c.table);
+    if !decl.is_empty() {
+        cmd.push_str(&format!(" '{{ {decl} }}'"));
+    }
+    if with_table {
+        cmd = format!("nft add table {} && {cmd}", c.table);
+    }
+    cmd
+}
+
+/// First base chain on the input hook, where a missing fragment would go.
+fn input_chain(ruleset: &Ruleset) -> Option<(&ruleset::Table, &ruleset::Chain)> {
+    ruleset
+        .tables
+        .iter()
+        .find_map(|t| t.base_chain("input").map(|c| (t, c)))
+}
+
+fn rule_desc(r: &NftRuleCheck) -> String {
+    format!(
+        "{} dport {} {}",
+        r.protocol,
+        r.dport,
+        r.verdict.as_deref().unwrap_or("accept")
+    )
+}
+
+fn rule_label(r: &NftRuleCheck) -> String {
+    format!("{}/{}/{}", r.table, r.chain, rule_desc(r))
+}
+
+/// Position of the first rule in `chain` matching the check's port and verdict.
+fn find_rule(table: &ruleset::Table, chain: &ruleset::Chain, r: &NftRuleCheck) -> Option<usize> {
+    let verdict = r.verdict.as_deref().unwrap_or("accept");
+    chain.rules.iter().position(|rule| {
+        rule.matches_port(table, &r.protocol, r.dport)
+            && rule.verdict().is_some_and(|v| v.to_string() == verdict)
+    })
+}
+
+/// Resolve a `"<family> <name>"` table reference.
+fn lookup_table<'a>(ruleset: &'a Ruleset, spec: &str) -> Option<&'a ruleset::Table> {
+    let (family, name) = spec.split_once(' ')?;
+    ruleset.table(family.trim(), name.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::CheckStatus::{self, Fail, Pass, Skipped, Warn};
+
+    fn structure(fixture: &str, checks: &str) -> Vec<Finding> {
+        let path = format!(
+            "{}/tests/fixtures/nftables/{fixture}",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let ruleset = Ruleset::parse(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let checks: NftChecks = toml::from_str(checks).unwrap();
+        let mut findings = Vec::new();
+        validate_structure(&ruleset, &checks, &mut findings);
+        findings
+    }
+
+    fn results(findings: &[Finding]) -> Vec<(&str, CheckStatus)> {
+        findings.iter().map(|f| (f.id.as_str(), f.status)).collect()
+    }
+
+    fn remediation<'a>(findings: &'a [Finding], id: &str) -> &'a str {
+        let finding = findings.iter().find(|f| f.id == id).unwrap();
+        finding.remediation.as_deref().unwrap()
+    }
+
+    #[test]
+    fn tables_and_chains() {
+        let findings = structure(
+            "inet-filter.json",
+            r#"
+tables = ["inet filter", "ip nat"]
+
+[[chains]]
+table = "inet filter"
+hook = "input"
+priority = 10
+policy = "accept"
+
+[[chains]]
+table = "inet filter"
+chain = "output"
+policy = "accept"
+
+[[chains]]
+table = "ip nat"
+hook = "postrouting"
+priority = 100
+
+[[chains]]
+table = "inet filter"
+"#,
+        );
+        assert_eq!(
+            results(&findings),
+            [
+                ("nft-table:inet filter", Pass),
+                ("nft-table:ip nat", Warn),
+                ("nft-chain:inet filter/hook:input", Pass),
+                ("nft-chain-hook:inet filter/hook:input", Pass),
+                ("nft-chain-priority:inet filter/hook:input", Warn),
+                ("nft-chain-policy:inet filter/hook:input", Warn),
+                ("nft-chain:inet filter/output", Pass),
+                ("nft-chain-policy:inet filter/output", Pass),
+                ("nft-chain:ip nat/hook:postrouting", Warn),
+                ("nft-chain-hook:ip nat/hook:postrouting", Skipped),
+                ("nft-chain-priority:ip nat/hook:postrouting", Skipped),
+                ("nft-chain-invalid:inet filter", Warn),
+            ]
+        );
+        assert_eq!(
+            remediation(&findings, "nft-table:ip nat"),
+            "nft add table ip nat"
+        );
+        assert_eq!(
+            remediation(&findings, "nft-chain-priority:inet filter/hook:input"),
+            "recreate the chain: nft delete chain inet filter input && nft add chain inet filter input '{ type filter hook input priority 10; policy accept; }'"
+        );
+        assert_eq!(
+            remediation(&findings, "nft-chain-policy:inet filter/hook:input"),
+            "nft chain inet filter input '{ policy accept; }'"
+        );
+        assert_eq!(
+            remediation(&findings, "nft-chain:ip nat/hook:postrouting"),
+            "nft add table ip nat && nft add chain ip nat postrouting '{ type filter hook postrouting priority 100; }'"
+        );
+    }
+
+    #[test]
+    fn required_rules() {
+        let findings = structure(
+            "jump-goto.json",
+            r#"
+[[rules]]
+table = "ip fw"
+chain = "services"
+protocol = "tcp"
+dport = 22
+before_drop = true
+
+[[rules]]
+table = "ip fw"
+chain = "services"
+protocol = "tcp"
+dport = 443
+
+[[rules]]
+table = "ip fw"
+chain = "missing"
+protocol = "tcp"
+dport = 80
+before_drop = true
+
+"#,
+        );
+        assert_eq!(
+            results(&findings),
+            [
+                ("nft-rule:ip fw/services/tcp dport 22 accept", Pass),
+                ("nft-rule-order:ip fw/services/tcp dport 22 accept", Pass),
+                ("nft-rule:ip fw/services/tcp dport 443 accept", Warn),
+                ("nft-rule:ip fw/missing/tcp dport 80 accept", Warn),
+                ("nft-rule-order:ip fw/missing/tcp dport 80 accept", Skipped),
+            ]
+        );
+        assert_eq!(
+            remediation(&findings, "nft-rule:ip fw/services/tcp dport 443 accept"),
+            "nft insert rule ip fw services tcp dport 443 accept"
+        );
+        assert_eq!(
+            remediation(&findings, "nft-rule:ip fw/missing/tcp dport 80 accept"),
+            "nft add chain ip fw missing && nft insert rule ip fw missing tcp dport 80 accept"
+        );
+    }
+
+    #[test]
+    fn forbidden_rules() {
+        let findings = structure(
+            "jump-goto.json",
+            r#"
+[[forbidden_rules]]
+table = "ip fw"
+chain = "unused"
+protocol = "tcp"
+dport = 23
+
+[[forbidden_rules]]
+table = "ip fw"
+chain = "services"
+protocol = "tcp"
+dport = 22
+verdict = "drop"
+"#,
+        );
+        assert_eq!(
+            results(&findings),
+            [
+                ("nft-forbidden-rule:ip fw/unused/tcp dport 23 accept", Fail),
+                ("nft-forbidden-rule:ip fw/services/tcp dport 22 drop", Pass),
+            ]
+        );
+        assert_eq!(
+            remediation(
+                &findings,
+                "nft-forbidden-rule:ip fw/unused/tcp dport 23 accept"
+            ),
+            "nft delete rule ip fw unused handle 9"
+        );
+    }
+
+    #[tokio::test]
+    async fn forbidden_ports_and_fragments() {
+        let ruleset = r#"{"nftables": [
+            {"table": {"family": "inet", "name": "filter"}},
+            {"chain": {"family": "inet", "table": "filter", "name": "input",
+                "type": "filter", "hook": "input", "prio": 0, "policy": "accept"}},
+            {"rule": {"family": "inet", "table": "filter", "chain": "input", "handle": 4,
+                "expr": [{"match": {"op": "==", "left": {"payload": {"protocol": "tcp", "field": "dport"}}, "right": 23}}, {"drop": null}]}}
+        ]}"#;
+        let mut outputs = Outputs::default();
+        outputs.insert(
+            LIST_RULESET,
+            crate::backends::CommandOutput {
+                code: 0,
+                stdout: ruleset.into(),
+                stderr: String::new(),
+            },
+        );
+        let policy: Policy = toml::from_str(
+            r#"
+schema = 1
+[checks]
+forbidden_ports = ["23/tcp", "3389/tcp", "ssh"]
+forbidden_fragments = ["policy accept", "ct state invalid drop"]
+"#,
+        )
+        .unwrap();
+        let backend = NftablesBackend;
+        let snap = backend.snapshot_from(&outputs).unwrap();
+        let findings = backend.validate(&policy, &snap).await.unwrap();
+        assert_eq!(
+            results(&findings),
+            [
+                ("nftables-available", Pass),
+                ("forbidden-fragment:policy accept", Fail),
+                ("forbidden-fragment:ct state invalid drop", Pass),
+                ("forbidden-port:23/tcp", Pass),
+                ("forbidden-port:3389/tcp", Fail),
+                ("invalid-port:ssh", Warn),
+            ]
+        );
+        // nothing to delete for the chain header: close the policy instead
+        assert_eq!(
+            remediation(&findings, "forbidden-port:3389/tcp"),
+            "nft chain inet filter input '{ policy drop; }'"
+        );
+        assert_eq!(
+            findings[1].evidence.as_ref().unwrap().snippet.as_deref(),
+            Some(
+                "table inet filter chain input: type filter hook input priority 0; policy accept;"
+            )
+        );
+    }
+}
@@ -0,0 +1,1124 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt;
+
+/// Typed view of `nft -j list ruleset`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Ruleset {
+    pub tables: Vec<Table>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Table {
+    pub family: String,
+    pub name: String,
+    pub chains: Vec<Chain>,
+    pub sets: Vec<Set>,
+    pub maps: Vec<Map>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Chain {
+    pub name: String,
+    pub chain_type: Option<String>,
+    pub hook: Option<String>,
+    pub priority: Option<i64>,
+    pub policy: Option<String>,
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Rule {
+    pub handle: Option<u64>,
+    pub comment: Option<String>,
+    pub exprs: Vec<Expr>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Set {
+    pub name: String,
+    pub set_type: String,
+    pub flags: Vec<String>,
+    pub elements: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Map {
+    pub name: String,
+    pub key_type: String,
+    pub value_type: String,
+    pub elements: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Expr {
+    Match {
+        op: String,
+        left: Value,
+        right: Value,
+    },
+    Verdict(Verdict),
+    Counter,
+    Other(Value),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verdict {
+    Accept,
+    Drop,
+    Reject,
+    Return,
+    Continue,
+    Queue,
+    Jump(String),
+    Goto(String),
+}
+
+/// Where a lookup matched: a rule, or the chain header when `rule` is `None`.
+#[derive(Clone, Copy, Debug)]
+pub struct Hit<'a> {
+    pub table: &'a Table,
+    pub chain: &'a Chain,
+    pub rule: Option<&'a Rule>,
+}
+
+impl Ruleset {
+    /// Parse the JSON document printed by `nft -j list ruleset`.
+    pub fn parse(json: &str) -> Result<Ruleset> {
+        let doc: Value = serde_json::from_str(json).context("invalid nft JSON output")?;
+        let items = doc
+            .get("nftables")
+            .and_then(Value::as_array)
+            .context("nft JSON output has no top-level \"nftables\" array")?;
+
+        let mut ruleset = Ruleset::default();
+        for item in items {
+            let Some((kind, obj)) = item
+                .as_object()
+                .and_then(|o| o.iter().next())
+                .and_then(|(k, v)| v.as_object().map(|o| (k, o)))
+            else {
+                continue;
+            };
+            match kind.as_str() {
+                "table" => {
+                    ruleset.tables.push(Table {
+                        family: str_field(obj, "family"),
+                        name: str_field(obj, "name"),
+                        chains: Vec::new(),
+                        sets: Vec::new(),
+                        maps: Vec::new(),
+                    });
+                }
+                "chain" => {
+                    let chain = Chain {
+                        name: str_field(obj, "name"),
+                        chain_type: opt_str_field(obj, "type"),
+                        hook: opt_str_field(obj, "hook"),
+                        priority: obj.get("prio").and_then(Value::as_i64),
+                        policy: opt_str_field(obj, "policy"),
+                        rules: Vec::new(),
+                    };
+                    ruleset.table_entry(obj)?.chains.push(chain);
+                }
+                "rule" => {
+                    let chain_name = str_field(obj, "chain");
+                    let rule = Rule {
+                        handle: obj.get("handle").and_then(Value::as_u64),
+                        comment: opt_str_field(obj, "comment"),
+                        exprs: obj
+                            .get("expr")
+                            .and_then(Value::as_array)
+                            .map(|exprs| exprs.iter().map(parse_expr).collect())
+                            .unwrap_or_default(),
+                    };
+                    ruleset
+                        .table_entry(obj)?
+                        .chains
+                        .iter_mut()
+                        .find(|c| c.name == chain_name)
+                        .with_context(|| format!("rule references unknown chain {chain_name}"))?
+                        .rules
+                        .push(rule);
+                }
+                "set" => {
+                    let set = Set {
+                        name: str_field(obj, "name"),
+                        set_type: type_field(obj.get("type")),
+                        flags: obj
+                            .get("flags")
+                            .and_then(Value::as_array)
+                            .map(|f| {
+                                f.iter()
+                                    .filter_map(Value::as_str)
+                                    .map(String::from)
+                                    .collect()
+                            })
+                            .unwrap_or_default(),
+                        elements: obj
+                            .get("elem")
+                            .and_then(Value::as_array)
+                            .map(|e| e.iter().map(render_value).collect())
+                            .unwrap_or_default(),
+                    };
+                    ruleset.table_entry(obj)?.sets.push(set);
+                }
+                "map" => {
+                    let map = Map {
+                        name: str_field(obj, "name"),
+                        key_type: type_field(obj.get("type")),
+                        value_type: type_field(obj.get("map")),
+                        elements: obj
+                            .get("elem")
+                            .and_then(Value::as_array)
+                            .map(|e| e.iter().map(render_map_elem).collect())
+                            .unwrap_or_default(),
+                    };
+                    ruleset.table_entry(obj)?.maps.push(map);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(ruleset)
+    }
+
+    pub fn table(&self, family: &str, name: &str) -> Option<&Table> {
+        self.tables
+            .iter()
+            .find(|t| t.family == family && t.name == name)
+    }
+
+    /// True if `fragment` appears as a contiguous token sequence in a chain
+    /// header or rule that can actually see traffic (base chains and chains
+    /// reachable from them). Comments and counter values are ignored.
+    pub fn contains_fragment(&self, fragment: &str) -> bool {
+        self.find_fragment(fragment).is_some()
+    }
+
+    /// First chain header or rule containing `fragment` (see
+    /// [`Ruleset::contains_fragment`]).
+    pub fn find_fragment(&self, fragment: &str) -> Option<Hit<'_>> {
+        let needle = tokens(fragment);
+        if needle.is_empty() {
+            return None;
+        }
+        self.tables.iter().find_map(|t| {
+            t.reachable_chains().into_iter().find_map(|c| {
+                if contains_window(&tokens(&c.header()), &needle) {
+                    return Some(Hit::new(t, c, None));
+                }
+                c.rules
+                    .iter()
+                    .find(|r| contains_window(&tokens(&r.to_string()), &needle))
+                    .map(|r| Hit::new(t, c, Some(r)))
+            })
+        })
+    }
+
+    /// True if `proto` traffic to `port` is accepted on the input path.
+    pub fn accepts_port(&self, proto: &str, port: u16) -> bool {
+        self.accepting_rule(proto, port).is_some()
+    }
+
+    /// First rule on the input path accepting `proto` traffic to `port`:
+    /// through a port match (literal, named set or verdict map) or a rule
+    /// accepting every port. Failing that, an input base chain with
+    /// `policy accept` that no rule drops the port in (the chain header).
+    pub fn accepting_rule(&self, proto: &str, port: u16) -> Option<Hit<'_>> {
+        self.tables.iter().find_map(|t| {
+            let chains = t.chains_for_hook("input");
+            let rule = chains.iter().find_map(|c| {
+                c.rules
+                    .iter()
+                    .find(|r| r.accepts_port(t, proto, port))
+                    .map(|r| Hit::new(t, c, Some(r)))
+            });
+            rule.or_else(|| {
+                let dropped = chains.iter().flat_map(|c| &c.rules).any(|r| {
+                    matches!(r.verdict(), Some(Verdict::Drop | Verdict::Reject))
+                        && r.covers_port(t, proto, port)
+                });
+                chains
+                    .iter()
+                    .find(|c| {
+                        c.hook.as_deref() == Some("input")
+                            && c.chain_type.as_deref() == Some("filter")
+                            && c.policy.as_deref() == Some("accept")
+                    })
+                    .filter(|_| !dropped)
+                    .map(|c| Hit::new(t, c, None))
+            })
+        })
+    }
+
+    fn table_entry(&mut self, obj: &serde_json::Map<String, Value>) -> Result<&mut Table> {
+        let family = str_field(obj, "family");
+        let name = str_field(obj, "table");
+        self.tables
+            .iter_mut()
+            .find(|t| t.family == family && t.name == name)
+            .with_context(|| format!("object references unknown table {family} {name}"))
+    }
+}
+
+impl<'a> Hit<'a> {
+    pub fn new(table: &'a Table, chain: &'a Chain, rule: Option<&'a Rule>) -> Self {
+        Self { table, chain, rule }
+    }
+
+    /// `table inet filter chain input: tcp dport 23 accept`
+    pub fn snippet(&self) -> String {
+        let line = match self.rule {
+            Some(r) => r.to_string(),
+            None => self.chain.header(),
+        };
+        format!(
+            "table {} chain {}: {line}",
+            self.table.spec(),
+            self.chain.name
+        )
+    }
+
+    /// `nft delete rule` for the matched rule; `None` for chain headers.
+    /// Rules listed without a handle get the command with a placeholder.
+    pub fn delete_command(&self) -> Option<String> {
+        let rule = self.rule?;
+        let (table, chain) = (self.table.spec(), &self.chain.name);
+        Some(match rule.handle {
+            Some(handle) => format!("nft delete rule {table} {chain} handle {handle}"),
+            None => format!(
+                "nft delete rule {table} {chain} handle <handle> (see nft -a list chain {table} {chain})"
+            ),
+        })
+    }
+}
+
+impl Table {
+    /// `<family> <name>`, as nft commands take it.
+    pub fn spec(&self) -> String {
+        format!("{} {}", self.family, self.name)
+    }
+
+    pub fn chain(&self, name: &str) -> Option<&Chain> {
+        self.chains.iter().find(|c| c.name == name)
+    }
+
+    pub fn base_chain(&self, hook: &str) -> Option<&Chain> {
+        self.chains.iter().find(|c| c.hook.as_deref() == Some(hook))
+    }
+
+    pub fn set(&self, name: &str) -> Option<&Set> {
+        self.sets.iter().find(|s| s.name == name)
+    }
+
+    pub fn map(&self, name: &str) -> Option<&Map> {
+        self.maps.iter().find(|m| m.name == name)
+    }
+
+    /// Base chains plus every regular chain reachable from them via jump/goto.
+    pub fn reachable_chains(&self) -> Vec<&Chain> {
+        self.reachable_from(self.chains.iter().filter(|c| c.is_base()).collect())
+    }
+
+    /// Base chains registered on `hook` plus the chains reachable from them.
+    pub fn chains_for_hook(&self, hook: &str) -> Vec<&Chain> {
+        self.reachable_from(
+            self.chains
+                .iter()
+                .filter(|c| c.hook.as_deref() == Some(hook))
+                .collect(),
+        )
+    }
+
+    fn reachable_from<'a>(&'a self, mut queue: VecDeque<&'a Chain>) -> Vec<&'a Chain> {
+        let mut seen = BTreeSet::new();
+        let mut out = Vec::new();
+
+        while let Some(chain) = queue.pop_front() {
+            if !seen.insert(chain.name.as_str()) {
+                continue;
+            }
+            out.push(chain);
+            for rule in &chain.rules {
+                for target in rule.jump_targets() {
+                    if let Some(next) = self.chain(&target) {
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        out
+    }
+}
+
+impl Chain {
+    pub fn is_base(&self) -> bool {
+        self.hook.is_some()
+    }
+
+    /// Chain declaration as printed by `nft list ruleset`, without braces.
+    pub fn header(&self) -> String {
+        let mut parts = Vec::new();
+        if let (Some(ty), Some(hook)) = (&self.chain_type, &self.hook) {
+            parts.push(format!(
+                "type {ty} hook {hook} priority {};",
+                self.priority.unwrap_or(0)
+            ));
+        }
+        if let Some(policy) = &self.policy {
+            parts.push(format!("policy {policy};"));
+        }
+        parts.join(" ")
+    }
+}
+
+impl Rule {
+    /// The terminal (or jump/goto) verdict of the rule, if any.
+    pub fn verdict(&self) -> Option<&Verdict> {
+        self.exprs.iter().rev().find_map(|e| match e {
+            Expr::Verdict(v) => Some(v),
+            _ => None,
+        })
+    }
+
+    /// True if the rule matches `proto` traffic to destination port `port`
+    /// (directly, via a set/range or a named set of `table`, or via
+    /// `meta l4proto` + `th dport`).
+    pub fn matches_port(&self, table: &Table, proto: &str, port: u16) -> bool {
+        let mut port_hit = None;
+        let mut proto_ok = true;
+        for e in &self.exprs {
+            let Expr::Match { op, left, right } = e else {
+                continue;
+            };
+            let positive = op == "==" || op == "in";
+            match classify_operand(left) {
+                Operand::Dport(p) => {
+                    let hit = positive && value_contains_port(table, right, port);
+                    if p == "th" {
+                        port_hit = Some(port_hit.unwrap_or(true) && hit);
+                    } else {
+                        port_hit = Some(port_hit.unwrap_or(true) && hit && p == proto);
+                    }
+                }
+                Operand::L4proto => {
+                    proto_ok &= positive && right.as_str() == Some(proto);
+                }
+                Operand::Other => {}
+            }
+        }
+        port_hit == Some(true) && proto_ok
+    }
+
+    /// True if the rule accepts `proto` traffic to `port`: an `accept` rule
+    /// matching the port or accepting every port, or a port verdict map whose
+    /// entry for the port is `accept`.
+    pub fn accepts_port(&self, table: &Table, proto: &str, port: u16) -> bool {
+        if self.verdict() == Some(&Verdict::Accept) {
+            return self.matches_port(table, proto, port)
+                || self.accepts_any_port(table, proto, port);
+        }
+        let mut proto_ok = true;
+        let mut accepted = false;
+        for e in &self.exprs {
+            match e {
+                Expr::Match { op, left, right } => {
+                    if let Operand::L4proto = classify_operand(left) {
+                        proto_ok &= (op == "==" || op == "in") && right.as_str() == Some(proto);
+                    }
+                }
+                Expr::Other(v) => {
+                    let Some(vmap) = v.get("vmap") else {
+                        continue;
+                    };
+                    let key_ok = match vmap.get("key").map(classify_operand) {
+                        Some(Operand::Dport(p)) => p == "th" || p == proto,
+                        _ => false,
+                    };
+                    accepted |= key_ok
+                        && vmap
+                            .get("data")
+                            .is_some_and(|data| vmap_accepts(table, data, port));
+                }
+                _ => {}
+            }
+        }
+        accepted && proto_ok
+    }
+
+    /// True for an `accept` rule without address or conntrack matches that
+    /// lets `port` through without naming it, e.g. a bare `accept`,
+    /// `iifname "eth0" accept` or `tcp dport != 25 accept`. Loopback accepts
+    /// do not count.
+    fn accepts_any_port(&self, table: &Table, proto: &str, port: u16) -> bool {
+        if self.verdict() != Some(&Verdict::Accept) {
+            return false;
+        }
+        self.exprs.iter().all(|e| {
+            let Expr::Match { op, left, right } = e else {
+                return true;
+            };
+            let positive = op == "==" || op == "in";
+            match classify_operand(left) {
+                Operand::L4proto => positive && right.as_str() == Some(proto),
+                Operand::Dport(p) => {
+                    (p == "th" || p == proto)
+                        && op == "!="
+                        && !value_contains_port(table, right, port)
+                }
+                Operand::Other => {
+                    let iface = left
+                        .get("meta")
+                        .and_then(|m| m.get("key"))
+                        .and_then(Value::as_str)
+                        .is_some_and(|k| k == "iif" || k == "iifname");
+                    iface && positive && right.as_str() != Some("lo")
+                }
+            }
+        })
+    }
+
+    /// True if every packet of `proto` traffic to `port` hits this rule:
+    /// either it has no matches at all, or it only matches on that port.
+    pub fn covers_port(&self, table: &Table, proto: &str, port: u16) -> bool {
+        let matches: Vec<&Expr> = self
+            .exprs
+            .iter()
+            .filter(|e| matches!(e, Expr::Match { .. }))
+            .collect();
+        if matches.is_empty() {
+            return true;
+        }
+        let only_port = matches.iter().all(|e| {
+            let Expr::Match { left, .. } = e else {
+                return false;
+            };
+            !matches!(classify_operand(left), Operand::Other)
+        });
+        only_port && self.matches_port(table, proto, port)
+    }
+
+    fn jump_targets(&self) -> Vec<String> {
+        let mut targets = Vec::new();
+        for e in &self.exprs {
+            match e {
+                Expr::Verdict(Verdict::Jump(t)) | Expr::Verdict(Verdict::Goto(t)) => {
+                    targets.push(t.clone())
+                }
+                // verdict maps and similar carry jump targets inside nested JSON
+                Expr::Other(v) => collect_jump_targets(v, &mut targets),
+                _ => {}
+            }
+        }
+        targets
+    }
+}
+
+impl fmt::Display for Ruleset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for t in &self.tables {
+            writeln!(f, "table {} {} {{", t.family, t.name)?;
+            for s in &t.sets {
+                writeln!(f, "\tset {} {{", s.name)?;
+                writeln!(f, "\t\ttype {}", s.set_type)?;
+                if !s.flags.is_empty() {
+                    writeln!(f, "\t\tflags {}", s.flags.join(","))?;
+                }
+                if !s.elements.is_empty() {
+                    writeln!(f, "\t\telements = {{ {} }}", s.elements.join(", "))?;
+                }
+                writeln!(f, "\t}}")?;
+            }
+            for m in &t.maps {
+                writeln!(f, "\tmap {} {{", m.name)?;
+                writeln!(f, "\t\ttype {} : {}", m.key_type, m.value_type)?;
+                if !m.elements.is_empty() {
+                    writeln!(f, "\t\telements = {{ {} }}", m.elements.join(", "))?;
+                }
+                writeln!(f, "\t}}")?;
+            }
+            for c in &t.chains {
+                writeln!(f, "\tchain {} {{", c.name)?;
+                let header = c.header();
+                if !header.is_empty() {
+                    writeln!(f, "\t\t{header}")?;
+                }
+                for r in &c.rules {
+                    writeln!(f, "\t\t{r}")?;
+                }
+                writeln!(f, "\t}}")?;
+            }
+            writeln!(f, "}}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.exprs.iter().map(|e| e.to_string()).collect();
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Match { op, left, right } => {
+                let lhs = render_operand(left);
+                let quote = matches!(lhs.as_str(), "iifname" | "oifname");
+                let rhs = if quote {
+                    right
+                        .as_str()
+                        .map(|s| format!("\"{s}\""))
+                        .unwrap_or_else(|| render_value(right))
+                } else {
+                    render_value(right)
+                };
+                match op.as_str() {
+                    "==" | "in" => write!(f, "{lhs} {rhs}"),
+                    _ => write!(f, "{lhs} {op} {rhs}"),
+                }
+            }
+            Expr::Verdict(v) => write!(f, "{v}"),
+            Expr::Counter => write!(f, "counter"),
+            Expr::Other(v) => write!(f, "{}", render_statement(v)),
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Accept => write!(f, "accept"),
+            Verdict::Drop => write!(f, "drop"),
+            Verdict::Reject => write!(f, "reject"),
+            Verdict::Return => write!(f, "return"),
+            Verdict::Continue => write!(f, "continue"),
+            Verdict::Queue => write!(f, "queue"),
+            Verdict::Jump(t) => write!(f, "jump {t}"),
+            Verdict::Goto(t) => write!(f, "goto {t}"),
+        }
+    }
+}
+
+fn parse_expr(v: &Value) -> Expr {
+    let Some((key, body)) = v.as_object().and_then(|o| o.iter().next()) else {
+        return Expr::Other(v.clone());
+    };
+    let target = || {
+        body.get("target")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    match key.as_str() {
+        "match" => Expr::Match {
+            op: body
+                .get("op")
+                .and_then(Value::as_str)
+                .unwrap_or("==")
+                .to_string(),
+            left: body.get("left").cloned().unwrap_or(Value::Null),
+            right: body.get("right").cloned().unwrap_or(Value::Null),
+        },
+        "accept" => Expr::Verdict(Verdict::Accept),
+        "drop" => Expr::Verdict(Verdict::Drop),
+        "reject" => Expr::Verdict(Verdict::Reject),
+        "return" => Expr::Verdict(Verdict::Return),
+        "continue" => Expr::Verdict(Verdict::Continue),
+        "queue" => Expr::Verdict(Verdict::Queue),
+        "jump" => Expr::Verdict(Verdict::Jump(target())),
+        "goto" => Expr::Verdict(Verdict::Goto(target())),
+        "counter" => Expr::Counter,
+        _ => Expr::Other(v.clone()),
+    }
+}
+
+fn render_operand(v: &Value) -> String {
+    let Some((key, body)) = v.as_object().and_then(|o| o.iter().next()) else {
+        return render_value(v);
+    };
+    match key.as_str() {
+        "payload" => match (
+            body.get("protocol").and_then(Value::as_str),
+            body.get("field").and_then(Value::as_str),
+        ) {
+            (Some(proto), Some(field)) => format!("{proto} {field}"),
+            _ => format!(
+                "@{},{},{}",
+                body.get("base").and_then(Value::as_str).unwrap_or("?"),
+                body.get("offset").and_then(Value::as_u64).unwrap_or(0),
+                body.get("len").and_then(Value::as_u64).unwrap_or(0)
+            ),
+        },
+        "meta" => {
+            let k = body.get("key").and_then(Value::as_str).unwrap_or("?");
+            match k {
+                "iif" | "iifname" | "iiftype" | "oif" | "oifname" | "oiftype" => k.to_string(),
+                _ => format!("meta {k}"),
+            }
+        }
+        "ct" => format!(
+            "ct {}",
+            body.get("key").and_then(Value::as_str).unwrap_or("?")
+        ),
+        _ => v.to_string(),
+    }
+}
+
+fn render_value(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Array(items) => items.iter().map(render_value).collect::<Vec<_>>().join(","),
+        Value::Object(o) => {
+            if let Some(items) = o.get("set").and_then(Value::as_array) {
+                let inner: Vec<String> = items.iter().map(render_value).collect();
+                format!("{{ {} }}", inner.join(", "))
+            } else if let Some([lo, hi]) =
+                o.get("range").and_then(Value::as_array).map(Vec::as_slice)
+            {
+                format!("{}-{}", render_value(lo), render_value(hi))
+            } else if let Some(p) = o.get("prefix") {
+                format!(
+                    "{}/{}",
+                    p.get("addr").map(render_value).unwrap_or_default(),
+                    p.get("len").map(render_value).unwrap_or_default()
+                )
+            } else if let Some(elem) = o.get("elem") {
+                elem.get("val").map(render_value).unwrap_or_default()
+            } else if let Some(Value::String(name)) = o.get("@") {
+                format!("@{name}")
+            } else {
+                render_operand(v)
+            }
+        }
+        Value::Null => String::new(),
+    }
+}
+
+fn render_statement(v: &Value) -> String {
+    let Some((key, body)) = v.as_object().and_then(|o| o.iter().next()) else {
+        return v.to_string();
+    };
+    match key.as_str() {
+        "log" => match body.get("prefix").and_then(Value::as_str) {
+            Some(prefix) => format!("log prefix \"{prefix}\""),
+            None => "log".to_string(),
+        },
+        "limit" => format!(
+            "limit rate {}/{}",
+            body.get("rate").map(render_value).unwrap_or_default(),
+            body.get("per").and_then(Value::as_str).unwrap_or("second")
+        ),
+        "masquerade" | "notrack" => key.clone(),
+        "snat" | "dnat" => {
+            let addr = body.get("addr").map(render_value).unwrap_or_default();
+            match body.get("port").map(render_value) {
+                Some(port) => format!("{key} to {addr}:{port}"),
+                None => format!("{key} to {addr}"),
+            }
+        }
+        "vmap" => format!(
+            "{} vmap {}",
+            body.get("key").map(render_operand).unwrap_or_default(),
+            body.get("data").map(render_value).unwrap_or_default()
+        ),
+        _ => v.to_string(),
+    }
+}
+
+fn render_map_elem(v: &Value) -> String {
+    match v.as_array().map(Vec::as_slice) {
+        Some([k, val]) => format!("{} : {}", render_value(k), render_map_value(val)),
+        _ => render_value(v),
+    }
+}
+
+fn render_map_value(v: &Value) -> String {
+    match parse_expr(v) {
+        Expr::Verdict(verdict) => verdict.to_string(),
+        _ => render_value(v),
+    }
+}
+
+enum Operand<'a> {
+    Dport(&'a str),
+    L4proto,
+    Other,
+}
+
+fn classify_operand(v: &Value) -> Operand<'_> {
+    if let Some(p) = v.get("payload") {
+        let proto = p.get("protocol").and_then(Value::as_str);
+        let field = p.get("field").and_then(Value::as_str);
+        return match (proto, field) {
+            (Some(proto), Some("dport")) => Operand::Dport(proto),
+            (Some("ip"), Some("protocol")) | (Some("ip6"), Some("nexthdr")) => Operand::L4proto,
+            _ => Operand::Other,
+        };
+    }
+    match v
+        .get("meta")
+        .and_then(|m| m.get("key"))
+        .and_then(Value::as_str)
+    {
+        Some("l4proto") => Operand::L4proto,
+        _ => Operand::Other,
+    }
+}
+
+fn value_contains_port(table: &Table, v: &Value, port: u16) -> bool {
+    if let Some(name) = set_reference(v) {
+        return table
+            .set(name)
+            .is_some_and(|s| s.elements.iter().any(|e| element_contains_port(e, port)));
+    }
+    match v {
+        Value::Number(n) => n.as_u64() == Some(u64::from(port)),
+        Value::String(s) => s.parse::<u16>().ok() == Some(port),
+        Value::Array(items) => items.iter().any(|i| value_contains_port(table, i, port)),
+        Value::Object(o) => {
+            if let Some(items) = o.get("set") {
+                value_contains_port(table, items, port)
+            } else if let Some([lo, hi]) =
+                o.get("range").and_then(Value::as_array).map(Vec::as_slice)
+            {
+                match (lo.as_u64(), hi.as_u64()) {
+                    (Some(lo), Some(hi)) => (lo..=hi).contains(&u64::from(port)),
+                    _ => false,
+                }
+            } else {
+                false
+            }
+        }
+        _ => false,
+    }
+}
+
+/// True if the verdict map `data` (anonymous, or a named map of `table`)
+/// maps `port` to `accept`.
+fn vmap_accepts(table: &Table, data: &Value, port: u16) -> bool {
+    if let Some(name) = set_reference(data) {
+        return table.map(name).is_some_and(|m| {
+            m.elements.iter().any(|e| {
+                e.split_once(" : ").is_some_and(|(key, verdict)| {
+                    verdict == "accept" && element_contains_port(key, port)
+                })
+            })
+        });
+    }
+    let Some(items) = data.get("set").and_then(Value::as_array) else {
+        return false;
+    };
+    items
+        .iter()
+        .any(|item| match item.as_array().map(Vec::as_slice) {
+            Some([key, verdict]) => {
+                matches!(parse_expr(verdict), Expr::Verdict(Verdict::Accept))
+                    && value_contains_port(table, key, port)
+            }
+            _ => false,
+        })
+}
+
+/// Name of a named set or map reference: `"@name"` or `{"@": "name"}`.
+fn set_reference(v: &Value) -> Option<&str> {
+    match v {
+        Value::String(s) => s.strip_prefix('@'),
+        Value::Object(o) => o.get("@").and_then(Value::as_str),
+        _ => None,
+    }
+}
+
+/// True if a rendered set element (`443` or `8000-8080`) contains `port`.
+fn element_contains_port(element: &str, port: u16) -> bool {
+    match element.split_once('-') {
+        Some((lo, hi)) => match (lo.trim().parse::<u16>(), hi.trim().parse::<u16>()) {
+            (Ok(lo), Ok(hi)) => (lo..=hi).contains(&port),
+            _ => false,
+        },
+        None => element.trim().parse::<u16>().ok() == Some(port),
+    }
+}
+
+fn collect_jump_targets(v: &Value, out: &mut Vec<String>) {
+    match v {
+        Value::Object(o) => {
+            for (k, inner) in o {
+                if k == "jump" || k == "goto" {
+                    if let Some(t) = inner.get("target").and_then(Value::as_str) {
+                        out.push(t.to_string());
+                    }
+                }
+                collect_jump_targets(inner, out);
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|i| collect_jump_targets(i, out)),
+        _ => {}
+    }
+}
+
+fn tokens(s: &str) -> Vec<&str> {
+    s.split_whitespace()
+        .map(|t| t.trim_end_matches([';', ',']))
+        .filter(|t| !t.is_empty() && *t != "counter")
+        .collect()
+}
+
+fn contains_window(haystack: &[&str], needle: &[&str]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+fn str_field(obj: &serde_json::Map<String, Value>, key: &str) -> String {
+    opt_str_field(obj, key).unwrap_or_default()
+}
+
+fn opt_str_field(obj: &serde_json::Map<String, Value>, key: &str) -> Option<String> {
+    obj.get(key).and_then(Value::as_str).map(String::from)
+}
+
+fn type_field(v: Option<&Value>) -> String {
+    match v {
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" . "),
+        Some(v) => render_value(v),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Ruleset {
+        let path = format!(
+            "{}/tests/fixtures/nftables/{name}",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        Ruleset::parse(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn rules(chain: &Chain) -> Vec<String> {
+        chain.rules.iter().map(Rule::to_string).collect()
+    }
+
+    #[test]
+    fn parses_a_basic_inet_filter() {
+        let ruleset = fixture("inet-filter.json");
+        let table = ruleset.table("inet", "filter").unwrap();
+        assert_eq!(table.chains.len(), 3);
+
+        let input = table.base_chain("input").unwrap();
+        assert_eq!(
+            input.header(),
+            "type filter hook input priority 0; policy drop;"
+        );
+        assert_eq!(
+            rules(input),
+            [
+                "ct state established,related accept",
+                "iifname \"lo\" accept",
+                "tcp dport 22 counter accept",
+                "meta l4proto udp th dport 60000-61000 accept",
+            ]
+        );
+        assert_eq!(input.rules[2].comment.as_deref(), Some("ssh"));
+
+        assert!(ruleset.accepts_port("tcp", 22));
+        assert!(!ruleset.accepts_port("tcp", 23));
+        assert!(ruleset.accepts_port("udp", 60500));
+        assert!(!ruleset.accepts_port("tcp", 60500));
+
+        // counters are ignored when matching fragments
+        let hit = ruleset.find_fragment("tcp dport 22 accept").unwrap();
+        assert_eq!(
+            hit.delete_command().as_deref(),
+            Some("nft delete rule inet filter input handle 6")
+        );
+        let header = ruleset
+            .find_fragment("hook input priority 0; policy drop")
+            .unwrap();
+        assert!(header.rule.is_none());
+        assert_eq!(
+            header.snippet(),
+            "table inet filter chain input: type filter hook input priority 0; policy drop;"
+        );
+    }
+
+    #[test]
+    fn parses_set_and_map_references() {
+        let ruleset = fixture("sets-maps.json");
+        let table = ruleset.table("inet", "filter").unwrap();
+
+        let ports = &table.sets[0];
+        assert_eq!(ports.name, "allowed_ports");
+        assert_eq!(ports.set_type, "inet_service");
+        assert_eq!(ports.flags, ["interval"]);
+        assert_eq!(ports.elements, ["22", "443", "8000-8080"]);
+        assert_eq!(table.sets[1].elements, ["10.0.0.0/8"]);
+
+        let map = &table.maps[0];
+        assert_eq!(
+            (map.key_type.as_str(), map.value_type.as_str()),
+            ("inet_service", "verdict")
+        );
+        assert_eq!(map.elements, ["80 : accept", "23 : drop"]);
+
+        let input = table.base_chain("input").unwrap();
+        assert_eq!(
+            rules(input)[..3],
+            [
+                "ip saddr @trusted accept",
+                "tcp dport @allowed_ports accept",
+                "tcp dport vmap @port_verdicts",
+            ]
+        );
+        assert!(ruleset.contains_fragment("tcp dport @allowed_ports accept"));
+
+        // wan_in is only reachable through the anonymous verdict map
+        let hit = ruleset
+            .find_fragment("udp dport { 51820, 51821 } accept")
+            .unwrap();
+        assert_eq!(hit.chain.name, "wan_in");
+        assert!(ruleset.accepts_port("udp", 51821));
+    }
+
+    #[test]
+    fn accepts_ports_through_named_sets_and_verdict_maps() {
+        let ruleset = fixture("sets-maps.json");
+        let handle = |proto, port| {
+            ruleset
+                .accepting_rule(proto, port)
+                .and_then(|hit| hit.rule?.handle)
+        };
+        // tcp dport @allowed_ports accept
+        assert_eq!(handle("tcp", 443), Some(7));
+        assert_eq!(handle("tcp", 8005), Some(7));
+        // tcp dport vmap @port_verdicts, 80 : accept
+        assert_eq!(handle("tcp", 80), Some(8));
+        // 23 : drop, and the set only applies to tcp
+        assert!(!ruleset.accepts_port("tcp", 23));
+        assert!(!ruleset.accepts_port("udp", 443));
+    }
+
+    #[test]
+    fn accepts_ports_through_policy_and_port_less_rules() {
+        let ruleset = |policy: &str, rules: &str| {
+            Ruleset::parse(&format!(
+                r#"{{"nftables": [
+                    {{"table": {{"family": "inet", "name": "filter"}}}},
+                    {{"chain": {{"family": "inet", "table": "filter", "name": "input",
+                        "type": "filter", "hook": "input", "prio": 0, "policy": "{policy}"}}}}
+                    {rules}
+                ]}}"#
+            ))
+            .unwrap()
+        };
+        let rule = |expr: &str| {
+            format!(
+                r#", {{"rule": {{"family": "inet", "table": "filter", "chain": "input", "expr": {expr}}}}}"#
+            )
+        };
+        let loopback = rule(
+            r#"[{"match": {"op": "==", "left": {"meta": {"key": "iifname"}}, "right": "lo"}}, {"accept": null}]"#,
+        );
+        let established = rule(
+            r#"[{"match": {"op": "in", "left": {"ct": {"key": "state"}}, "right": ["established", "related"]}}, {"accept": null}]"#,
+        );
+        let drop_telnet = rule(
+            r#"[{"match": {"op": "==", "left": {"payload": {"protocol": "tcp", "field": "dport"}}, "right": 23}}, {"drop": null}]"#,
+        );
+
+        let open = ruleset("accept", "");
+        let hit = open.accepting_rule("tcp", 23).unwrap();
+        assert!(hit.rule.is_none());
+        assert!(hit.snippet().ends_with("policy accept;"));
+        assert!(!ruleset("accept", &drop_telnet).accepts_port("tcp", 23));
+        assert!(ruleset("accept", &drop_telnet).accepts_port("tcp", 22));
+
+        // return traffic and loopback do not expose ports
+        let closed = ruleset("drop", &format!("{loopback}{established}"));
+        assert!(!closed.accepts_port("tcp", 23));
+        let wan = rule(
+            r#"[{"match": {"op": "==", "left": {"meta": {"key": "iifname"}}, "right": "eth0"}}, {"accept": null}]"#,
+        );
+        assert!(ruleset("drop", &wan).accepts_port("tcp", 23));
+        assert!(ruleset("drop", &rule(r#"[{"accept": null}]"#)).accepts_port("udp", 53));
+        let all_but_smtp = ruleset(
+            "drop",
+            &rule(
+                r#"[{"match": {"op": "!=", "left": {"payload": {"protocol": "tcp", "field": "dport"}}, "right": 25}}, {"accept": null}]"#,
+            ),
+        );
+        assert!(all_but_smtp.accepts_port("tcp", 23));
+        assert!(!all_but_smtp.accepts_port("tcp", 25));
+    }
+
+    #[test]
+    fn follows_jump_and_goto() {
+        let ruleset = fixture("jump-goto.json");
+        let table = ruleset.table("ip", "fw").unwrap();
+
+        let input = table.chain("input").unwrap();
+        assert_eq!(
+            input.rules[0].verdict(),
+            Some(&Verdict::Jump("services".into()))
+        );
+        assert_eq!(
+            input.rules[1].verdict(),
+            Some(&Verdict::Goto("logdrop".into()))
+        );
+        assert_eq!(
+            rules(table.chain("logdrop").unwrap()),
+            ["log prefix \"fw-drop: \" drop"]
+        );
+
+        let reachable: Vec<&str> = table
+            .reachable_chains()
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(reachable, ["input", "services", "logdrop"]);
+
+        assert!(ruleset.accepts_port("tcp", 22));
+        // the accept in the unreferenced chain never sees traffic
+        assert!(!ruleset.accepts_port("tcp", 23));
+        assert!(!ruleset.contains_fragment("tcp dport 23 accept"));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let err = |json: &str| format!("{:#}", Ruleset::parse(json).unwrap_err());
+
+        assert!(err("table inet filter {").contains("invalid nft JSON output"));
+        assert!(err(r#"{"tables": []}"#).contains("no top-level \"nftables\" array"));
+        assert!(err(
+            r#"{"nftables": [{"chain": {"family": "inet", "table": "filter", "name": "input"}}]}"#
+        )
+        .contains("unknown table inet filter"));
+        assert!(err(r#"{"nftables": [
+                {"table": {"family": "inet", "name": "filter"}},
+                {"rule": {"family": "inet", "table": "filter", "chain": "input", "expr": []}}
+            ]}"#)
+        .contains("unknown chain input"));
+
+        // objects sentinel does not model are skipped
+        let ruleset = Ruleset::parse(
+            r#"{"nftables": [{"metainfo": {"version": "1.0.9"}}, {"flowtable": {"name": "f"}}, 42]}"#,
+        )
+        .unwrap();
+        assert!(ruleset.tables.is_empty());
+    }
+}
@@ -0,0 +1,323 @@
+use anyhow::{bail, Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+use crate::{
+    backends::{Backend, BackendStatus, Outputs},
+    cmd::run_with_input,
+    config::Policy,
+    status::{CheckStatus, Evidence, Finding, Overall},
+};
+
+pub const PREFIX: &str = "sentinel-backend-";
+const PROTOCOL: u32 = 1;
+
+/// External backend: an executable `sentinel-backend-<name>` on `PATH`. Each
+/// operation (`detect`, `snapshot`, `validate`) runs it once with a single
+/// JSON request on stdin and expects a single JSON response on stdout and
+/// exit code 0; see the README for the message formats.
+pub struct PluginBackend {
+    name: String,
+    path: PathBuf,
+}
+
+#[derive(Serialize)]
+struct Request<'a> {
+    protocol: u32,
+    op: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    policy: Option<&'a Policy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snapshot: Option<&'a PluginSnapshot>,
+}
+
+#[derive(Deserialize)]
+struct DetectResponse {
+    detected: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PluginSnapshot {
+    active: bool,
+    #[serde(default)]
+    facts: BTreeMap<String, String>,
+    #[serde(default)]
+    raw: String,
+}
+
+#[derive(Deserialize)]
+struct ValidateResponse {
+    findings: Vec<PluginFinding>,
+}
+
+#[derive(Deserialize)]
+struct PluginFinding {
+    id: String,
+    severity: Overall,
+    msg: String,
+    /// Only `skipped` matters; otherwise the status follows the severity.
+    #[serde(default)]
+    status: Option<CheckStatus>,
+    #[serde(default)]
+    evidence: Option<Evidence>,
+    #[serde(default)]
+    expected: Option<String>,
+    #[serde(default)]
+    actual: Option<String>,
+    #[serde(default)]
+    remediation: Option<String>,
+}
+
+impl PluginBackend {
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    async fn call<T: DeserializeOwned>(&self, req: &Request<'_>, timeout_ms: u64) -> Result<T> {
+        let input = serde_json::to_vec(req)?;
+        let program = self.path.to_string_lossy();
+        let (code, out, err) = run_with_input(&program, &[], &input, timeout_ms).await?;
+        if code != 0 {
+            bail!(
+                "backend plugin {} failed on {} (exit {code}): {}",
+                self.name,
+                req.op,
+                err.trim()
+            );
+        }
+        serde_json::from_str(&out).with_context(|| {
+            format!(
+                "backend plugin {} returned invalid JSON for {}",
+                self.name, req.op
+            )
+        })
+    }
+
+    fn request<'a>(op: &'a str) -> Request<'a> {
+        Request {
+            protocol: PROTOCOL,
+            op,
+            policy: None,
+            snapshot: None,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Backend for PluginBackend {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn detect(&self) -> Result<bool> {
+        let res: DetectResponse = self.call(&Self::request("detect"), 2000).await?;
+        Ok(res.detected)
+    }
+
+    fn snapshot_commands(&self) -> &'static [&'static [&'static str]] {
+        &[]
+    }
+
+    fn snapshot_from(&self, _outputs: &Outputs) -> Result<BackendStatus> {
+        bail!(
+            "backend plugin {} does not support recorded snapshots",
+            self.name
+        )
+    }
+
+    async fn snapshot(&self) -> Result<BackendStatus> {
+        let snap: PluginSnapshot = self.call(&Self::request("snapshot"), 5000).await?;
+        Ok(BackendStatus {
+            backend_name: self.name.clone(),
+            active: snap.active,
+            facts: snap.facts.into_iter().collect(),
+            raw: snap.raw,
+            ruleset: None,
+            iptables: None,
+        })
+    }
+
+    async fn validate(&self, policy: &Policy, snap: &BackendStatus) -> Result<Vec<Finding>> {
+        let plugin_snap = PluginSnapshot {
+            active: snap.active,
+            facts: snap.facts.iter().cloned().collect(),
+            raw: snap.raw.clone(),
+        };
+        let req = Request {
+            policy: Some(policy),
+            snapshot: Some(&plugin_snap),
+            ..Self::request("validate")
+        };
+        let res: ValidateResponse = self.call(&req, 5000).await?;
+        Ok(res
+            .findings
+            .into_iter()
+            .map(|f| {
+                let finding = match f.status {
+                    Some(CheckStatus::Skipped) => Finding::skipped(f.id, f.msg),
+                    _ => Finding::new(f.id, f.severity, f.msg),
+                };
+                Finding {
+                    evidence: f.evidence,
+                    expected: f.expected,
+                    actual: f.actual,
+                    remediation: f.remediation,
+                    ..finding
+                }
+            })
+            .collect())
+    }
+}
+
+/// Executables named `sentinel-backend-<name>` on `PATH`, sorted by name;
+/// the first directory providing a name wins. Relative and empty `PATH`
+/// entries (the current directory) are ignored.
+pub fn discover() -> Vec<PluginBackend> {
+    let mut found: BTreeMap<String, PathBuf> = BTreeMap::new();
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    for dir in std::env::split_paths(&path).filter(|d| d.is_absolute()) {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let Some(name) = file_name.to_str().and_then(|n| n.strip_prefix(PREFIX)) else {
+                continue;
+            };
+            if name.is_empty() || found.contains_key(name) {
+                continue;
+            }
+            let executable = std::fs::metadata(entry.path())
+                .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0);
+            if executable {
+                found.insert(name.to_string(), entry.path());
+            }
+        }
+    }
+    found
+        .into_iter()
+        .map(|(name, path)| PluginBackend { name, path })
+        .collect()
+}
+
+pub fn find(name: &str) -> Option<PluginBackend> {
+    discover().into_iter().find(|p| p.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLUGIN: &str = r#"#!/bin/sh
+req=$(cat)
+printf '%s\n' "$req" >> "$0.requests"
+case "$req" in
+*'"op":"detect"'*) echo '{"detected": true}' ;;
+*'"op":"snapshot"'*) echo '{"active": true, "facts": {"zone": "public"}, "raw": "22/tcp"}' ;;
+*'"op":"validate"'*) cat <<'JSON'
+{"findings": [
+  {"id": "missing-port:22/tcp", "severity": "ok", "msg": "22/tcp is open",
+   "evidence": {"rule": "22/tcp", "snippet": "22/tcp"}},
+  {"id": "forbidden-port:23/tcp", "severity": "error", "msg": "23/tcp is open",
+   "expected": "closed", "actual": "open", "remediation": "close 23/tcp"},
+  {"id": "zone:dmz", "severity": "warn", "status": "skipped", "msg": "no dmz zone"}
+]}
+JSON
+;;
+*'"op":"bad"'*) echo 'not json' ;;
+*) echo "unknown op" >&2; exit 3 ;;
+esac
+"#;
+
+    fn plugin(name: &str) -> (PathBuf, PluginBackend) {
+        let dir =
+            std::env::temp_dir().join(format!("sentinel-plugin-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{PREFIX}{name}"));
+        std::fs::write(&path, PLUGIN).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let backend = PluginBackend {
+            name: name.into(),
+            path,
+        };
+        (dir, backend)
+    }
+
+    #[tokio::test]
+    async fn speaks_the_json_protocol() {
+        let (dir, backend) = plugin("proto");
+        let policy: Policy =
+            toml::from_str("schema = 1\n[checks]\nrequired_ports = [\"22/tcp\"]\n").unwrap();
+
+        assert!(backend.detect().await.unwrap());
+        let snap = backend.snapshot().await.unwrap();
+        assert!(snap.active);
+        assert_eq!(snap.facts, [("zone".to_string(), "public".to_string())]);
+        let findings = backend.validate(&policy, &snap).await.unwrap();
+        let got: Vec<_> = findings
+            .iter()
+            .map(|f| (f.id.as_str(), f.status, f.severity))
+            .collect();
+        assert_eq!(
+            got,
+            [
+                ("missing-port:22/tcp", CheckStatus::Pass, Overall::Ok),
+                ("forbidden-port:23/tcp", CheckStatus::Fail, Overall::Error),
+                ("zone:dmz", CheckStatus::Skipped, Overall::Ok),
+            ]
+        );
+        let evidence = findings[0].evidence.as_ref().unwrap();
+        assert_eq!(evidence.rule.as_deref(), Some("22/tcp"));
+        assert_eq!(findings[1].expected.as_deref(), Some("closed"));
+        assert_eq!(findings[1].actual.as_deref(), Some("open"));
+        assert_eq!(findings[1].remediation.as_deref(), Some("close 23/tcp"));
+
+        // one request per operation; validate gets the policy and the snapshot back
+        let requests =
+            std::fs::read_to_string(dir.join(format!("{PREFIX}proto.requests"))).unwrap();
+        let requests: Vec<serde_json::Value> = requests
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(
+            requests[0],
+            serde_json::json!({"protocol": 1, "op": "detect"})
+        );
+        assert_eq!(
+            requests[2]["policy"]["checks"]["required_ports"],
+            serde_json::json!(["22/tcp"])
+        );
+        assert_eq!(
+            requests[2]["snapshot"],
+            serde_json::json!({"active": true, "facts": {"zone": "public"}, "raw": "22/tcp"})
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn reports_plugin_failures() {
+        let (dir, backend) = plugin("broken");
+        let err = backend
+            .call::<DetectResponse>(&PluginBackend::request("other"), 2000)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "backend plugin broken failed on other (exit 3): unknown op"
+        );
+        let err = backend
+            .call::<DetectResponse>(&PluginBackend::request("bad"), 2000)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "backend plugin broken returned invalid JSON for bad"
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,710 @@
+use anyhow::Result;
+use std::net::IpAddr;
+use tracing::debug;
+
+use crate::{
+    backends::{
+        skip_rules, Backend, BackendStatus, Outputs, FRAGMENT_RULES, PORT_RULES, SERVICE_RULES,
+    },
+    cmd::run_timeout,
+    config::{parse_port_spec, Policy},
+    status::{Evidence, Finding, Overall},
+};
+
+pub struct UfwBackend;
+
+const STATUS_VERBOSE: &[&str] = &["ufw", "status", "verbose"];
+
+#[async_trait::async_trait]
+impl Backend for UfwBackend {
+    fn name(&self) -> &str {
+        "ufw"
+    }
+
+    async fn detect(&self) -> Result<bool> {
+        match run_timeout("ufw", &["status"], 2000).await {
+            Ok((code, out, _err)) => {
+                Ok(code == 0 && out.trim_start().starts_with("Status: active"))
+            }
+            Err(err) => {
+                debug!(error = ?err, "ufw detection failed");
+                Ok(false)
+            }
+        }
+    }
+
+    fn snapshot_commands(&self) -> &'static [&'static [&'static str]] {
+        &[STATUS_VERBOSE]
+    }
+
+    fn snapshot_from(&self, outputs: &Outputs) -> Result<BackendStatus> {
+        let status = outputs.stdout_ok(STATUS_VERBOSE).map(parse_status);
+
+        let mut facts = vec![];
+        if let Some(s) = &status {
+            for (key, value) in [
+                ("default_incoming", &s.default_incoming),
+                ("default_outgoing", &s.default_outgoing),
+                ("default_routed", &s.default_routed),
+            ] {
+                if let Some(v) = value {
+                    facts.push((key.to_string(), v.clone()));
+                }
+            }
+            facts.push(("rules".into(), s.rules.len().to_string()));
+        }
+        let raw = outputs
+            .get(STATUS_VERBOSE)
+            .map(|o| if o.code == 0 { &o.stdout } else { &o.stderr })
+            .cloned()
+            .unwrap_or_default();
+
+        Ok(BackendStatus {
+            backend_name: self.name().to_string(),
+            active: status.as_ref().is_some_and(|s| s.active),
+            facts,
+            raw,
+            ruleset: None,
+            iptables: None,
+        })
+    }
+
+    async fn validate(&self, policy: &Policy, snap: &BackendStatus) -> Result<Vec<Finding>> {
+        let mut findings = Vec::new();
+        let checks = policy.checks.as_ref();
+
+        if checks
+            .and_then(|c| c.require_firewall_active)
+            .unwrap_or(true)
+        {
+            if !snap.active {
+                findings.push(
+                    Finding::new(
+                        "ufw-inactive",
+                        Overall::Error,
+                        "ufw is not active (ufw status verbose != Status: active)",
+                    )
+                    .with_values("Status: active", "Status: inactive")
+                    .with_remediation("ufw enable"),
+                );
+                findings.extend(skip_rules(
+                    checks,
+                    &[SERVICE_RULES, PORT_RULES].concat(),
+                    "ufw is not active",
+                ));
+                findings.extend(skip_rules(checks, FRAGMENT_RULES, NO_FRAGMENTS));
+                return Ok(findings);
+            }
+            findings.push(Finding::pass("ufw-active", "ufw is active"));
+        }
+
+        let status = parse_status(&snap.raw);
+
+        if let Some(ufw) = checks.and_then(|c| c.ufw.as_ref()) {
+            for (direction, expected, actual) in [
+                ("incoming", &ufw.default_incoming, &status.default_incoming),
+                ("outgoing", &ufw.default_outgoing, &status.default_outgoing),
+                ("routed", &ufw.default_routed, &status.default_routed),
+            ] {
+                let Some(expected) = expected else {
+                    continue;
+                };
+                let id = format!("ufw-default:{direction}");
+                let actual = actual.as_deref();
+                findings.push(
+                    if actual.is_some_and(|a| a.eq_ignore_ascii_case(expected)) {
+                        Finding::pass(id, format!("default {direction} policy is {expected}"))
+                    } else {
+                        let actual = actual.unwrap_or("unknown");
+                        Finding::new(
+                            id,
+                            Overall::Warn,
+                            format!("default {direction} policy is {actual}, expected {expected}"),
+                        )
+                        .with_values(
+                            format!("{expected} ({direction})"),
+                            format!("{actual} ({direction})"),
+                        )
+                        .with_remediation(format!("ufw default {expected} {direction}"))
+                    },
+                );
+            }
+        }
+
+        // services map onto ufw application profiles (`ufw allow OpenSSH`) or,
+        // as with `ufw allow ssh`, onto the ports /etc/services lists for them
+        let services_db = if checks
+            .is_some_and(|c| c.required_services.is_some() || c.forbidden_services.is_some())
+        {
+            std::fs::read_to_string(SERVICES_DB).unwrap_or_default()
+        } else {
+            String::new()
+        };
+
+        if let Some(services) = checks.and_then(|c| c.required_services.as_ref()) {
+            for s in services {
+                let id = format!("missing-service:{s}");
+                let ports = service_ports(&services_db, s);
+                findings.push(match status.service_access(s, &ports) {
+                    Some(line) => Finding::pass(id, format!("ufw allows service: {s}"))
+                        .with_evidence(Evidence::rule(s).with_snippet(line)),
+                    None => Finding::new(
+                        id,
+                        Overall::Warn,
+                        format!("no ufw rule allows application profile or service: {s}"),
+                    )
+                    .with_evidence(with_snippet(
+                        Evidence::rule(s),
+                        status.rules_for(|r| {
+                            r.app().is_some_and(|a| a.eq_ignore_ascii_case(s))
+                                || ports
+                                    .iter()
+                                    .any(|(port, proto)| r.matches_port(proto, *port))
+                        }),
+                    ))
+                    .with_values(format!("ALLOW IN {s}"), "no rule allows the service")
+                    .with_remediation(format!("ufw allow {}", quote(s))),
+                });
+            }
+        }
+
+        if let Some(services) = checks.and_then(|c| c.forbidden_services.as_ref()) {
+            for s in services {
+                let id = format!("forbidden-service:{s}");
+                if let Some(rule) = status.app_rule(s) {
+                    findings.push(
+                        Finding::new(
+                            id,
+                            Overall::Error,
+                            format!("ufw allows forbidden application profile: {s}"),
+                        )
+                        .with_evidence(Evidence::rule(s).with_snippet(rule.to_line()))
+                        .with_values(
+                            format!("no rule allows {s}"),
+                            format!("{} {} {s}", rule.action, rule.direction),
+                        )
+                        .with_remediation(format!(
+                            "ufw delete {} {}",
+                            rule.action.to_lowercase(),
+                            quote(s)
+                        )),
+                    );
+                    continue;
+                }
+                let open = service_ports(&services_db, s)
+                    .into_iter()
+                    .find_map(|(port, proto)| {
+                        status
+                            .port_access(&proto, port)
+                            .map(|access| (format!("{port}/{proto}"), access))
+                    });
+                findings.push(match open {
+                    Some((p, access)) => Finding::new(
+                        id,
+                        Overall::Error,
+                        format!("ufw allows forbidden service: {s} ({p})"),
+                    )
+                    .with_evidence(Evidence::rule(s).with_snippet(access.line(&status)))
+                    .with_values(format!("no rule allows {s}"), access.line(&status))
+                    .with_remediation(close_port(&status, &access, &p)),
+                    None => Finding::pass(
+                        id,
+                        format!("no ufw rule allows forbidden application profile or service: {s}"),
+                    ),
+                });
+            }
+        }
+
+        if let Some(ports) = checks.and_then(|c| c.required_ports.as_ref()) {
+            for p in ports {
+                let Some((port, proto)) = parse_port_spec(p) else {
+                    findings.push(invalid_port(p));
+                    continue;
+                };
+                let id = format!("missing-port:{p}");
+                findings.push(match status.port_access(proto, port) {
+                    Some(access) => {
+                        Finding::pass(id, format!("ufw allows required incoming port: {p}"))
+                            .with_evidence(Evidence::rule(p).with_snippet(access.line(&status)))
+                    }
+                    None => Finding::new(
+                        id,
+                        Overall::Warn,
+                        format!("ufw does not allow required incoming port: {p}"),
+                    )
+                    .with_evidence(with_snippet(
+                        Evidence::rule(p),
+                        status.rules_for(|r| r.direction == "IN" && r.matches_port(proto, port)),
+                    ))
+                    .with_values(format!("{p} ALLOW IN"), status.default_line())
+                    .with_remediation(format!("ufw allow {p}")),
+                });
+            }
+        }
+
+        if let Some(ports) = checks.and_then(|c| c.forbidden_ports.as_ref()) {
+            for p in ports {
+                let Some((port, proto)) = parse_port_spec(p) else {
+                    findings.push(invalid_port(p));
+                    continue;
+                };
+                let id = format!("forbidden-port:{p}");
+                findings.push(match status.port_access(proto, port) {
+                    Some(access) => Finding::new(
+                        id,
+                        Overall::Error,
+                        format!("ufw allows forbidden incoming port: {p}"),
+                    )
+                    .with_evidence(Evidence::rule(p).with_snippet(access.line(&status)))
+                    .with_values(format!("{p} not reachable"), access.line(&status))
+                    .with_remediation(close_port(&status, &access, p)),
+                    None => Finding::pass(
+                        id,
+                        format!("ufw does not allow forbidden incoming port: {p}"),
+                    ),
+                });
+            }
+        }
+
+        findings.extend(skip_rules(checks, FRAGMENT_RULES, NO_FRAGMENTS));
+        Ok(findings)
+    }
+
+    fn normalize(&self, snap: &BackendStatus) -> String {
+        let status = parse_status(&snap.raw);
+        let mut out = String::new();
+        for (key, value) in [
+            ("incoming", &status.default_incoming),
+            ("outgoing", &status.default_outgoing),
+            ("routed", &status.default_routed),
+        ] {
+            out.push_str(&format!(
+                "default {key} {}\n",
+                value.as_deref().unwrap_or("-")
+            ));
+        }
+        for r in &status.rules {
+            out.push_str(&r.to_line());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Where ufw (via getservbyname) looks up `ufw allow <service>`.
+const SERVICES_DB: &str = "/etc/services";
+
+const NO_FRAGMENTS: &str =
+    "rule fragments are matched by the nftables and iptables backends, not on ufw rules";
+
+fn with_snippet(evidence: Evidence, snippet: Option<String>) -> Evidence {
+    match snippet {
+        Some(snippet) => evidence.with_snippet(snippet),
+        None => evidence,
+    }
+}
+
+/// Command closing `p` (`22/tcp`). Deleting the allowing rule only works for
+/// plain port rules; anything else is shadowed by a deny inserted in front.
+fn close_port(status: &UfwStatus, access: &Access<'_>, p: &str) -> String {
+    match access {
+        Access::Rule(r) if r.unrestricted() && r.app().is_none() => {
+            format!("ufw delete {} {}", r.action.to_lowercase(), r.to)
+        }
+        _ if status.rules.is_empty() => format!("ufw deny {p}"),
+        _ => format!("ufw insert 1 deny {p}"),
+    }
+}
+
+/// Ports `/etc/services` (`db`) lists for `name` or one of its aliases:
+/// `ssh 22/tcp` gives `[(22, "tcp")]`.
+fn service_ports(db: &str, name: &str) -> Vec<(u16, String)> {
+    let mut ports = Vec::new();
+    for line in db.lines() {
+        let line = line.split_once('#').map_or(line, |(l, _)| l);
+        let mut fields = line.split_whitespace();
+        let (Some(service), Some(spec)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        if service != name && !fields.any(|alias| alias == name) {
+            continue;
+        }
+        if let Some((port, proto)) = spec
+            .split_once('/')
+            .and_then(|(port, proto)| Some((port.parse().ok()?, proto.to_string())))
+        {
+            if !ports.contains(&(port, proto.clone())) {
+                ports.push((port, proto));
+            }
+        }
+    }
+    ports
+}
+
+/// Application profile names may contain spaces (`Nginx Full`).
+fn quote(app: &str) -> String {
+    if app.contains(char::is_whitespace) {
+        format!("'{app}'")
+    } else {
+        app.to_string()
+    }
+}
+
+/// Parsed `ufw status verbose`.
+#[derive(Debug, Default)]
+struct UfwStatus {
+    active: bool,
+    default_incoming: Option<String>,
+    default_outgoing: Option<String>,
+    default_routed: Option<String>,
+    rules: Vec<UfwRule>,
+}
+
+/// One row of the rule table, e.g. `22/tcp (v6)  ALLOW IN  Anywhere (v6)`.
+#[derive(Debug)]
+struct UfwRule {
+    to: String,
+    action: String,
+    direction: String,
+    from: String,
+    v6: bool,
+}
+
+/// Why a port is reachable through ufw.
+#[derive(Debug)]
+enum Access<'a> {
+    /// First rule covering the port allows it.
+    Rule(&'a UfwRule),
+    /// No rule blocks it and the default incoming policy is allow.
+    DefaultAllow,
+}
+
+impl Access<'_> {
+    fn line(&self, status: &UfwStatus) -> String {
+        match self {
+            Access::Rule(r) => r.to_line(),
+            Access::DefaultAllow => status.default_line(),
+        }
+    }
+}
+
+impl UfwStatus {
+    /// ufw evaluates rules first-match, separately per address family: the
+    /// port is reachable if, in some family, the first incoming rule covering
+    /// it allows it, or no rule blocks it and the default incoming policy is
+    /// allow. DENY rules limited to a source or interface do not block it.
+    fn port_access(&self, proto: &str, port: u16) -> Option<Access<'_>> {
+        let default_allow = self
+            .default_incoming
+            .as_deref()
+            .is_some_and(|d| d.eq_ignore_ascii_case("allow"));
+
+        [false, true].into_iter().find_map(|v6| {
+            for r in self
+                .rules
+                .iter()
+                .filter(|r| r.v6 == v6 && r.direction == "IN" && r.matches_port(proto, port))
+            {
+                match r.action.as_str() {
+                    "ALLOW" | "LIMIT" => return Some(Access::Rule(r)),
+                    "DENY" | "REJECT" if r.unrestricted() => return None,
+                    _ => {}
+                }
+            }
+            default_allow.then_some(Access::DefaultAllow)
+        })
+    }
+
+    /// Lines allowing `service`: a rule for the application profile of that
+    /// name or, failing that, access to every one of its `ports`.
+    fn service_access(&self, service: &str, ports: &[(u16, String)]) -> Option<String> {
+        if let Some(rule) = self.app_rule(service) {
+            return Some(rule.to_line());
+        }
+        if ports.is_empty() {
+            return None;
+        }
+        ports
+            .iter()
+            .map(|(port, proto)| self.port_access(proto, *port).map(|a| a.line(self)))
+            .collect::<Option<Vec<_>>>()
+            .map(|lines| lines.join("\n"))
+    }
+
+    fn app_rule(&self, app: &str) -> Option<&UfwRule> {
+        self.rules.iter().find(|r| {
+            r.direction == "IN"
+                && matches!(r.action.as_str(), "ALLOW" | "LIMIT")
+                && r.app().is_some_and(|a| a.eq_ignore_ascii_case(app))
+        })
+    }
+
+    /// Rules mentioning the application profile or covering the port, for
+    /// evidence on failed checks.
+    fn rules_for(&self, matches: impl Fn(&UfwRule) -> bool) -> Option<String> {
+        let lines: Vec<String> = self
+            .rules
+            .iter()
+            .filter(|r| matches(r))
+            .map(UfwRule::to_line)
+            .collect();
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+
+    fn default_line(&self) -> String {
+        format!(
+            "Default: {} (incoming)",
+            self.default_incoming.as_deref().unwrap_or("unknown")
+        )
+    }
+}
+
+impl UfwRule {
+    /// `to` without a trailing `on <iface>`.
+    fn destination(&self) -> &str {
+        self.to
+            .split_once(" on ")
+            .map(|(dest, _)| dest)
+            .unwrap_or(&self.to)
+            .trim()
+    }
+
+    /// Port part of the destination (`22/tcp`, `80,443/tcp`, `6000:6007`),
+    /// application profile name, or neither for host-wide rules.
+    fn service(&self) -> &str {
+        let dest = self.destination();
+        match dest.split_once(' ') {
+            Some((first, rest)) if is_address(first) => rest.trim(),
+            _ if is_address(dest) => "",
+            _ => dest,
+        }
+    }
+
+    fn app(&self) -> Option<&str> {
+        let service = self.service();
+        (!service.is_empty() && !is_port_spec(service)).then_some(service)
+    }
+
+    fn matches_port(&self, proto: &str, port: u16) -> bool {
+        let service = self.service();
+        if service.is_empty() {
+            // host-wide rules (`ufw allow from 10.0.0.0/8`) only count when
+            // they are not limited to a source or interface
+            return self.unrestricted();
+        }
+        if !is_port_spec(service) {
+            return false;
+        }
+        let (ports, rule_proto) = match service.split_once('/') {
+            Some((ports, p)) => (ports, Some(p)),
+            None => (service, None),
+        };
+        rule_proto.is_none_or(|p| p == proto)
+            && ports.split(',').any(|spec| match spec.split_once(':') {
+                Some((lo, hi)) => matches!(
+                    (lo.parse::<u16>(), hi.parse::<u16>()),
+                    (Ok(lo), Ok(hi)) if (lo..=hi).contains(&port)
+                ),
+                None => spec.parse::<u16>().ok() == Some(port),
+            })
+    }
+
+    /// Applies to every source on every interface.
+    fn unrestricted(&self) -> bool {
+        !self.to.contains(" on ") && self.from == "Anywhere"
+    }
+
+    fn to_line(&self) -> String {
+        let v6 = if self.v6 { " (v6)" } else { "" };
+        format!(
+            "{}{v6} {} {} {}{v6}",
+            self.to, self.action, self.direction, self.from
+        )
+    }
+}
+
+/// Parse `ufw status verbose`: `Key: value` header lines, then a
+/// `To / Action / From` table whose columns are separated by whitespace.
+fn parse_status(raw: &str) -> UfwStatus {
+    let mut status = UfwStatus::default();
+    let mut in_table = false;
+
+    for line in raw.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if !in_table {
+            if let Some(rest) = line.strip_prefix("Status:") {
+                status.active = rest.trim() == "active";
+            } else if let Some(rest) = line.strip_prefix("Default:") {
+                // `deny (incoming), allow (outgoing), disabled (routed)`
+                for part in rest.split(',') {
+                    let Some((value, key)) = part.trim().split_once(' ') else {
+                        continue;
+                    };
+                    let value = Some(value.to_string());
+                    match key.trim_matches(|c| c == '(' || c == ')') {
+                        "incoming" => status.default_incoming = value,
+                        "outgoing" => status.default_outgoing = value,
+                        "routed" => status.default_routed = value,
+                        _ => {}
+                    }
+                }
+            } else if line.starts_with("--") {
+                in_table = true;
+            }
+            continue;
+        }
+        if let Some(rule) = parse_rule(line) {
+            status.rules.push(rule);
+        }
+    }
+
+    status
+}
+
+fn parse_rule(line: &str) -> Option<UfwRule> {
+    let line = line.split_once('#').map(|(l, _)| l).unwrap_or(line);
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let action_at = tokens
+        .iter()
+        .position(|t| matches!(*t, "ALLOW" | "DENY" | "REJECT" | "LIMIT"))?;
+
+    let (direction, from_at) = match tokens.get(action_at + 1) {
+        Some(&d @ ("IN" | "OUT" | "FWD")) => (d, action_at + 2),
+        _ => ("IN", action_at + 1),
+    };
+
+    let mut to = tokens[..action_at].join(" ");
+    let mut from = tokens[from_at..].join(" ");
+    let v6 = to.contains("(v6)") || from.contains("(v6)");
+    if v6 {
+        to = to.replace(" (v6)", "");
+        from = from.replace(" (v6)", "");
+    }
+
+    Some(UfwRule {
+        to,
+        action: tokens[action_at].to_string(),
+        direction: direction.to_string(),
+        from,
+        v6,
+    })
+}
+
+fn is_address(s: &str) -> bool {
+    s == "Anywhere"
+        || s.split_once('/')
+            .map_or(s, |(addr, _)| addr)
+            .parse::<IpAddr>()
+            .is_ok()
+}
+
+/// `22`, `22/tcp`, `80,443/tcp` or `6000:6007/udp`.
+fn is_port_spec(s: &str) -> bool {
+    let ports = s.split_once('/').map_or(s, |(ports, _)| ports);
+    !ports.is_empty()
+        && ports
+            .split(',')
+            .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit() || c == ':'))
+}
+
+fn invalid_port(p: &str) -> Finding {
+    Finding::new(
+        format!("invalid-port:{p}"),
+        Overall::Warn,
+        format!("port is not a <port>/<proto> spec: {p}"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVICES: &str = "\
+# Network services, Internet style
+ftp             21/tcp
+ssh             22/tcp                          # SSH Remote Login Protocol
+domain          53/tcp                          # Domain Name Server
+domain          53/udp
+http            80/tcp          www             # WorldWideWeb HTTP
+mysql           3306/tcp
+";
+
+    fn fixture() -> UfwStatus {
+        let path = format!(
+            "{}/tests/snapshots/ufw/ufw-status-verbose.out",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        parse_status(&std::fs::read_to_string(path).unwrap())
+    }
+
+    #[test]
+    fn parses_status_verbose() {
+        let status = fixture();
+        assert!(status.active);
+        assert_eq!(status.default_incoming.as_deref(), Some("deny"));
+        assert_eq!(status.default_outgoing.as_deref(), Some("allow"));
+        assert_eq!(status.default_routed.as_deref(), Some("disabled"));
+        assert_eq!(status.rules.len(), 11);
+
+        let lines: Vec<String> = status.rules.iter().map(UfwRule::to_line).collect();
+        assert_eq!(lines[0], "22/tcp ALLOW IN Anywhere");
+        assert_eq!(lines[8], "22/tcp (v6) ALLOW IN Anywhere (v6)");
+        assert_eq!(status.rules[1].app(), Some("Nginx Full"));
+        assert_eq!(status.rules[6].service(), "");
+        assert_eq!(status.rules[10].direction, "OUT");
+
+        assert!(!parse_status("Status: inactive\n").active);
+    }
+
+    #[test]
+    fn port_access_follows_first_match() {
+        let status = fixture();
+        assert!(matches!(
+            status.port_access("tcp", 22),
+            Some(Access::Rule(_))
+        ));
+        assert!(status.port_access("udp", 6003).is_some());
+        assert!(status.port_access("tcp", 5432).is_some());
+        // an unrestricted deny comes first; the source-limited allow is never reached
+        assert!(status.port_access("tcp", 3306).is_none());
+        // reachable on eth0 only, which still counts as reachable
+        assert!(status.port_access("tcp", 8080).is_some());
+        // host-wide allows limited to a source network do not open ports
+        assert!(status.port_access("tcp", 9000).is_none());
+        // outgoing rules do not count
+        assert!(status.port_access("tcp", 25).is_none());
+
+        let open = parse_status("Status: active\nDefault: allow (incoming)\n");
+        assert!(matches!(
+            open.port_access("tcp", 9000),
+            Some(Access::DefaultAllow)
+        ));
+    }
+
+    #[test]
+    fn resolves_services_through_etc_services() {
+        assert_eq!(service_ports(SERVICES, "ssh"), [(22, "tcp".to_string())]);
+        assert_eq!(service_ports(SERVICES, "www"), [(80, "tcp".to_string())]);
+        assert_eq!(service_ports(SERVICES, "domain").len(), 2);
+        assert!(service_ports(SERVICES, "OpenSSH").is_empty());
+
+        let status = fixture();
+        // `ufw allow ssh` is listed as 22/tcp
+        let ssh = service_ports(SERVICES, "ssh");
+        assert_eq!(
+            status.service_access("ssh", &ssh).as_deref(),
+            Some("22/tcp ALLOW IN Anywhere")
+        );
+        assert!(status
+            .service_access("mysql", &service_ports(SERVICES, "mysql"))
+            .is_none());
+        assert!(status
+            .service_access("domain", &service_ports(SERVICES, "domain"))
+            .is_none());
+        assert!(status.service_access("nginx full", &[]).is_some());
+        assert!(status.service_access("OpenSSH", &[]).is_none());
+    }
+}
@@ -0,0 +1,171 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use similar::{ChangeTag, TextDiff};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    config::{default_state_dir, ensure_state_dir},
+    status::{Evidence, Finding, Overall},
+    statusfile,
+};
+
+/// An approved, normalized snapshot plus its recorded SHA-256 digest.
+pub struct Baseline {
+    pub path: PathBuf,
+    pub rules: String,
+    pub digest: String,
+}
+
+pub fn rules_path(backend: &str) -> Result<PathBuf> {
+    Ok(default_state_dir()?.join(format!("baseline-{backend}.rules")))
+}
+
+fn digest_path(rules: &Path) -> PathBuf {
+    rules.with_extension("sha256")
+}
+
+pub fn sha256_hex(data: &str) -> String {
+    format!("{:x}", Sha256::digest(data.as_bytes()))
+}
+
+/// Write `<state>/baseline-<backend>.rules` and a `sha256sum`-style digest
+/// next to it (dir 0700, files 0600), each replaced atomically.
+pub fn save(backend: &str, normalized: &str) -> Result<Baseline> {
+    ensure_state_dir()?;
+    let path = rules_path(backend)?;
+
+    let digest = sha256_hex(normalized);
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    statusfile::write_atomic(&path, normalized.as_bytes(), 0o600)?;
+    statusfile::write_atomic(
+        &digest_path(&path),
+        format!("{digest}  {file_name}\n").as_bytes(),
+        0o600,
+    )?;
+
+    Ok(Baseline {
+        path,
+        rules: normalized.to_string(),
+        digest,
+    })
+}
+
+pub fn load(backend: &str) -> Result<Option<Baseline>> {
+    let path = rules_path(backend)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let rules = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read baseline: {}", path.display()))?;
+    let digest_file = digest_path(&path);
+    let digest = std::fs::read_to_string(&digest_file)
+        .with_context(|| format!("failed to read baseline digest: {}", digest_file.display()))?
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    Ok(Some(Baseline {
+        path,
+        rules,
+        digest,
+    }))
+}
+
+/// Compare the live normalized snapshot with the saved baseline. Returns a
+/// passed `ruleset-drift` check when they match; a baseline that cannot be
+/// read is reported as `baseline-unreadable` rather than an error.
+pub fn verify(backend: &str, normalized: &str) -> Vec<Finding> {
+    let baseline = match load(backend) {
+        Ok(Some(baseline)) => baseline,
+        Ok(None) => {
+            return vec![Finding::new(
+                "baseline-missing",
+                Overall::Warn,
+                format!("no baseline saved for {backend} (run `sentinelctl baseline save`)"),
+            )
+            .with_remediation(format!(
+                "sentinelctl --backend {backend} baseline save"
+            ))]
+        }
+        Err(err) => {
+            return vec![Finding::new(
+                "baseline-unreadable",
+                Overall::Error,
+                format!("failed to load the baseline for {backend}: {err:#}"),
+            )
+            .with_remediation(format!(
+                "restore read access to the baseline files, or re-approve with `sentinelctl --backend {backend} baseline save`"
+            ))]
+        }
+    };
+
+    if sha256_hex(&baseline.rules) != baseline.digest {
+        return vec![Finding::new(
+            "baseline-corrupt",
+            Overall::Error,
+            format!(
+                "baseline file does not match its recorded digest: {}",
+                baseline.path.display()
+            ),
+        )
+        .with_values(
+            format!("sha256 {}", baseline.digest),
+            format!("sha256 {}", sha256_hex(&baseline.rules)),
+        )
+        .with_remediation(format!(
+            "check who modified {}, then re-approve with `sentinelctl --backend {backend} baseline save`",
+            baseline.path.display()
+        ))];
+    }
+
+    if sha256_hex(normalized) == baseline.digest {
+        return vec![Finding::pass(
+            "ruleset-drift",
+            "live ruleset matches the approved baseline",
+        )];
+    }
+
+    let changes = changed_lines(&baseline.rules, normalized);
+    let added = changes.iter().filter(|l| l.starts_with('+')).count();
+    let removed = changes.len() - added;
+    let mut snippet = changes
+        .iter()
+        .take(SNIPPET_LINES)
+        .cloned()
+        .collect::<Vec<_>>();
+    if changes.len() > SNIPPET_LINES {
+        snippet.push(format!("... {} more", changes.len() - SNIPPET_LINES));
+    }
+    vec![Finding::new("ruleset-drift", Overall::Error, format!(
+            "live ruleset differs from approved baseline (+{added}/-{removed} lines, see `sentinelctl diff`)"
+        ))
+    .with_evidence(Evidence::default().with_snippet(snippet.join("\n")))
+    .with_values(
+        format!("sha256 {}", baseline.digest),
+        format!("sha256 {}", sha256_hex(normalized)),
+    )
+    .with_remediation(format!(
+        "review `sentinelctl --backend {backend} diff`, then revert the change or approve it with `sentinelctl --backend {backend} baseline save`"
+    ))]
+}
+
+/// Changed lines shown in a drift finding's evidence.
+const SNIPPET_LINES: usize = 20;
+
+/// Added (`+`) and removed (`-`) lines, in diff order.
+fn changed_lines(old: &str, new: &str) -> Vec<String> {
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .filter_map(|change| {
+            let sign = match change.tag() {
+                ChangeTag::Insert => '+',
+                ChangeTag::Delete => '-',
+                ChangeTag::Equal => return None,
+            };
+            Some(format!("{sign}{}", change.value().trim_end_matches('\n')))
+        })
+        .collect()
+}
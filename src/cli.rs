@@ -1,4 +1,4 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -7,14 +7,23 @@ use clap::{Parser, Subcommand, ValueEnum};
     about = "Sentinel: policy validation + status reporting"
 )]
 pub struct Cli {
-    /// Override backend selection (default: auto)
-    #[arg(long, value_enum, default_value_t = BackendChoice::Auto)]
+    /// Override backend selection: auto, firewalld, ufw, nftables, iptables or
+    /// the name of a `sentinel-backend-<name>` plugin on PATH
+    #[arg(long, default_value = "auto", value_parser = parse_backend_choice)]
     pub backend: BackendChoice,
 
     /// Path to policy file (default: XDG config sentinel/policy.toml)
     #[arg(long)]
     pub policy: Option<std::path::PathBuf>,
 
+    /// Policy profile to apply (default: first profile whose match rules hit, else `default`)
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// Replay command outputs recorded by `snapshot export` instead of querying the firewall
+    #[arg(long, value_name = "DIR")]
+    pub snapshot_from: Option<std::path::PathBuf>,
+
     #[command(subcommand)]
     pub cmd: Command,
 }
@@ -28,16 +37,117 @@ pub enum Command {
         /// Print a short single-line status for bars
         #[arg(long)]
         one_line: bool,
+        #[command(flatten)]
+        out: OutputArgs,
     },
 
     /// Run validations and return exit code (0/1/2)
     Check {
         #[arg(long)]
         json: bool,
+        /// Print every check (passed ones too) as JUnit XML or SARIF for CI
+        #[arg(long, value_enum, conflicts_with = "json")]
+        format: Option<CheckFormat>,
+        #[command(flatten)]
+        out: OutputArgs,
+    },
+
+    /// Show policy deviations and the live ruleset diff against a saved baseline
+    Diff {
+        #[arg(long)]
+        json: bool,
+        /// Baseline snapshot to compare against (default: XDG state baseline for the backend)
+        #[arg(long)]
+        baseline: Option<std::path::PathBuf>,
+        /// Disable ANSI colors in human output
+        #[arg(long)]
+        no_color: bool,
+    },
+
+    /// Keep running: re-check on an interval and immediately on firewall changes
+    Watch {
+        /// Re-check interval (e.g. 500ms, 30s, 2min)
+        #[arg(long, default_value = "30s", value_parser = parse_duration)]
+        interval: std::time::Duration,
+        #[command(flatten)]
+        out: OutputArgs,
+        /// Only re-check on the interval (no nft monitor / firewalld D-Bus events)
+        #[arg(long)]
+        no_monitor: bool,
+    },
+
+    /// Run the checks and print Prometheus metrics: overall state, findings,
+    /// backend detection and command latency (exit 0 once written;
+    /// the state is in the gauges)
+    Metrics {
+        /// Write atomically to this file instead of stdout, e.g. a `.prom` file
+        /// in node_exporter's textfile collector directory
+        #[arg(long)]
+        textfile: Option<std::path::PathBuf>,
     },
 
-    /// Show diff between current state and policy/baseline (MVP: policy-based snapshot)
-    Diff,
+    /// Keep running: re-check on an interval and serve the results over HTTP
+    Serve {
+        /// Serve the `metrics` output at http://<addr>/metrics (e.g. 127.0.0.1:9733)
+        #[arg(long)]
+        metrics_listen: std::net::SocketAddr,
+        /// Re-check interval (e.g. 500ms, 30s, 2min)
+        #[arg(long, default_value = "30s", value_parser = parse_duration)]
+        interval: std::time::Duration,
+    },
+
+    /// NetworkManager dispatcher entry point: re-check (and re-select the
+    /// profile) when connectivity changes. Install as a dispatcher script
+    /// running `sentinelctl nm-dispatch "$1" "$2"`.
+    NmDispatch {
+        /// Interface from the dispatcher (`none` or empty for host-wide events)
+        #[arg(value_parser = parse_iface)]
+        iface: String,
+        /// Dispatcher action; actions this version does not know are ignored
+        #[arg(value_parser = parse_nm_action)]
+        action: NmAction,
+        #[command(flatten)]
+        out: OutputArgs,
+        /// Exit with the check result (0/1/2) instead of always 0
+        #[arg(long)]
+        exit_status: bool,
+    },
+
+    /// List overall state transitions recorded by previous checks
+    History {
+        #[arg(long)]
+        json: bool,
+        /// Show at most this many of the most recent transitions
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Approved ruleset baseline (XDG state dir) for drift detection
+    Baseline {
+        #[command(subcommand)]
+        cmd: BaselineCmd,
+    },
+
+    /// Policy file utilities
+    Policy {
+        #[command(subcommand)]
+        cmd: PolicyCmd,
+    },
+
+    /// Print the JSON Schema of a policy file or of the status report
+    Schema {
+        #[arg(value_enum)]
+        kind: SchemaKind,
+        /// Policy schema version (default: latest)
+        #[arg(long)]
+        schema_version: Option<u32>,
+    },
+
+    /// Recorded backend command outputs for offline checks (`--snapshot-from`)
+    Snapshot {
+        #[command(subcommand)]
+        cmd: SnapshotCmd,
+    },
 
     /// Backend utilities
     Backend {
@@ -46,15 +156,244 @@ pub enum Command {
     },
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct OutputArgs {
+    /// Also write the status JSON to this file (atomic: temp file, fsync, rename)
+    #[arg(long)]
+    pub output: Option<std::path::PathBuf>,
+    /// File mode for --output, in octal
+    #[arg(long, default_value = "0644", value_parser = parse_mode, requires = "output")]
+    pub mode: u32,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BaselineCmd {
+    /// Save the current normalized ruleset and its SHA-256 digest as the baseline
+    Save,
+    /// Compare the live ruleset against the saved baseline (exit codes 0/1/2)
+    Verify {
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PolicyCmd {
+    /// Print the policy file
+    Show {
+        /// Print the result of merging includes and policy.d drop-ins, with
+        /// the file each value came from
+        #[arg(long)]
+        resolved: bool,
+    },
+    /// Report syntax errors, unknown keys, wrong value types and malformed port
+    /// specs as file:line:column (exit 0 when clean, 2 otherwise)
+    Lint,
+    /// Rewrite the policy (and its includes and drop-ins) in the latest schema,
+    /// keeping comments; prints a diff unless --write is given
+    Migrate {
+        /// Rewrite the files in place
+        #[arg(long)]
+        write: bool,
+    },
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum CheckFormat {
+    Junit,
+    Sarif,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum SchemaKind {
+    Policy,
+    Status,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SnapshotCmd {
+    /// Record the selected backend's command outputs into a directory
+    Export { dir: std::path::PathBuf },
+}
+
 #[derive(Subcommand, Debug)]
 pub enum BackendCmd {
     List,
     Detect,
 }
 
-#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum BackendChoice {
     Auto,
     Firewalld,
     Nftables,
+    Iptables,
+    Ufw,
+    /// External `sentinel-backend-<name>` executable.
+    Plugin(String),
+}
+
+pub fn parse_backend_choice(s: &str) -> Result<BackendChoice, String> {
+    Ok(match s.trim() {
+        "auto" => BackendChoice::Auto,
+        "firewalld" => BackendChoice::Firewalld,
+        "nftables" => BackendChoice::Nftables,
+        "iptables" => BackendChoice::Iptables,
+        "ufw" => BackendChoice::Ufw,
+        "" => return Err("backend name must not be empty".into()),
+        name if name.contains('/') => return Err(format!("invalid backend name: {name}")),
+        name => BackendChoice::Plugin(name.to_string()),
+    })
+}
+
+/// Actions NetworkManager passes to dispatcher scripts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NmAction {
+    Up,
+    Down,
+    PreUp,
+    PreDown,
+    VpnUp,
+    VpnDown,
+    VpnPreUp,
+    VpnPreDown,
+    Hostname,
+    Dhcp4Change,
+    Dhcp6Change,
+    ConnectivityChange,
+    Reapply,
+    /// An action added in a newer NetworkManager.
+    Other(String),
+}
+
+pub fn parse_nm_action(s: &str) -> Result<NmAction, String> {
+    Ok(match s {
+        "up" => NmAction::Up,
+        "down" => NmAction::Down,
+        "pre-up" => NmAction::PreUp,
+        "pre-down" => NmAction::PreDown,
+        "vpn-up" => NmAction::VpnUp,
+        "vpn-down" => NmAction::VpnDown,
+        "vpn-pre-up" => NmAction::VpnPreUp,
+        "vpn-pre-down" => NmAction::VpnPreDown,
+        "hostname" => NmAction::Hostname,
+        "dhcp4-change" => NmAction::Dhcp4Change,
+        "dhcp6-change" => NmAction::Dhcp6Change,
+        "connectivity-change" => NmAction::ConnectivityChange,
+        "reapply" => NmAction::Reapply,
+        other => NmAction::Other(other.to_string()),
+    })
+}
+
+impl NmAction {
+    /// Actions after which the reachable networks (and so the profile) may
+    /// have changed; `pre-*`, `hostname` and unknown events are ignored.
+    pub fn triggers_check(&self) -> bool {
+        !matches!(
+            self,
+            NmAction::PreUp
+                | NmAction::PreDown
+                | NmAction::VpnPreUp
+                | NmAction::VpnPreDown
+                | NmAction::Hostname
+                | NmAction::Other(_)
+        )
+    }
+}
+
+/// Validate a dispatcher interface argument with the kernel's rules for
+/// device names (at most 15 bytes, no `/`, `:` or whitespace).
+pub fn parse_iface(s: &str) -> Result<String, String> {
+    if s.is_empty() || s == "none" {
+        return Ok("none".into());
+    }
+    if s == "." || s == ".." || s.len() > 15 {
+        return Err(format!("invalid interface name: {s:?}"));
+    }
+    if s.chars()
+        .any(|c| c == '/' || c == ':' || c.is_whitespace() || c.is_control())
+    {
+        return Err(format!("invalid interface name: {s:?}"));
+    }
+    Ok(s.to_string())
+}
+
+/// Parse a systemd-style duration: `500ms`, `30s`, `2min`/`2m`, `1h` (bare numbers are seconds).
+pub fn parse_duration(s: &str) -> Result<std::time::Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    let n: u64 = num.parse().map_err(|_| format!("invalid duration: {s}"))?;
+    let scale: u64 = match unit.trim() {
+        "ms" => 1,
+        "" | "s" | "sec" => 1000,
+        "m" | "min" => 60_000,
+        "h" => 3_600_000,
+        other => return Err(format!("unknown duration unit: {other}")),
+    };
+    let ms = n
+        .checked_mul(scale)
+        .ok_or_else(|| format!("duration too large: {s}"))?;
+    if ms == 0 {
+        return Err("duration must be greater than zero".into());
+    }
+    Ok(std::time::Duration::from_millis(ms))
+}
+
+/// Parse an octal file mode such as `0644` or `600`.
+pub fn parse_mode(s: &str) -> Result<u32, String> {
+    let mode = u32::from_str_radix(s.trim(), 8).map_err(|_| format!("invalid octal mode: {s}"))?;
+    if mode > 0o7777 {
+        return Err(format!("mode out of range: {s}"));
+    }
+    Ok(mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn parses_duration_units() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration(" 45sec "), Ok(Duration::from_secs(45)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("2min"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        assert_eq!(
+            parse_duration("0s"),
+            Err("duration must be greater than zero".into())
+        );
+        assert_eq!(parse_duration("5d"), Err("unknown duration unit: d".into()));
+        assert_eq!(parse_duration("s"), Err("invalid duration: s".into()));
+        assert_eq!(parse_duration("-1s"), Err("invalid duration: -1s".into()));
+        assert_eq!(
+            parse_duration("18446744073709551615h"),
+            Err("duration too large: 18446744073709551615h".into())
+        );
+        assert!(parse_duration("18446744073709551615ms").is_ok());
+        assert!(parse_duration("99999999999999999999").is_err());
+    }
+
+    #[test]
+    fn unknown_dispatcher_actions_are_accepted_and_ignored() {
+        let cli =
+            Cli::try_parse_from(["sentinelctl", "nm-dispatch", "wlan0", "carrier-up"]).unwrap();
+        let Command::NmDispatch { action, .. } = cli.cmd else {
+            panic!("expected nm-dispatch");
+        };
+        assert_eq!(action, NmAction::Other("carrier-up".into()));
+        assert!(!action.triggers_check());
+        assert!(parse_nm_action("connectivity-change")
+            .unwrap()
+            .triggers_check());
+        assert!(!parse_nm_action("vpn-pre-up").unwrap().triggers_check());
+    }
 }
@@ -1,41 +1,207 @@
 use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
-use tokio::{process::Command, time};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines},
+    process::{Child, ChildStdout, Command},
+    time,
+};
+
+/// Per-program statistics of [`run_timeout`] and [`run_with_input`] calls
+/// in this process, exported as metrics.
+#[derive(Clone, Debug, Default)]
+pub struct CommandStats {
+    pub calls: u64,
+    /// Calls that could not be spawned or timed out.
+    pub errors: u64,
+    pub seconds: f64,
+}
+
+static STATS: Mutex<BTreeMap<String, CommandStats>> = Mutex::new(BTreeMap::new());
+
+/// Snapshot of the statistics, keyed by program file name.
+pub fn command_stats() -> BTreeMap<String, CommandStats> {
+    STATS.lock().map(|s| s.clone()).unwrap_or_default()
+}
+
+fn record<T>(program: &str, started: Instant, res: &Result<T>) {
+    let name = Path::new(program)
+        .file_name()
+        .map_or(program.into(), |n| n.to_string_lossy());
+    if let Ok(mut stats) = STATS.lock() {
+        let entry = stats.entry(name.into_owned()).or_default();
+        entry.calls += 1;
+        entry.errors += u64::from(res.is_err());
+        entry.seconds += started.elapsed().as_secs_f64();
+    }
+}
 
 pub async fn run_timeout(
     program: &str,
     args: &[&str],
     timeout_ms: u64,
 ) -> Result<(i32, String, String)> {
-    let mut cmd = Command::new(program);
-    cmd.args(args);
+    let started = Instant::now();
+    let res = run_child(program, args, timeout_ms).await;
+    record(program, started, &res);
+    res
+}
 
-    let mut child = cmd
+async fn run_child(program: &str, args: &[&str], timeout_ms: u64) -> Result<(i32, String, String)> {
+    let child = Command::new(program)
+        .args(args)
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
         .spawn()
         .with_context(|| format!("failed to spawn: {program}"))?;
 
-    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
-
-    loop {
-        if let Some(_status) = child.try_wait()? {
-            let out = child
-                .wait_with_output()
-                .await
-                .context("failed to collect command output")?;
-            let code = out.status.code().unwrap_or(2);
-            let stdout = String::from_utf8_lossy(&out.stdout).to_string();
-            let stderr = String::from_utf8_lossy(&out.stderr).to_string();
-            return Ok((code, stdout, stderr));
-        }
-
-        if Instant::now() >= deadline {
-            let _ = child.start_kill();
-            let _ = child.wait().await;
-            anyhow::bail!("command timed out: {program} {args:?}");
-        }
-
-        time::sleep(Duration::from_millis(25)).await;
+    // read both pipes while waiting: output larger than the pipe buffer would
+    // otherwise block the child until the timeout
+    let out = time::timeout(Duration::from_millis(timeout_ms), child.wait_with_output())
+        .await
+        .map_err(|_| anyhow::anyhow!("command timed out: {program} {args:?}"))?
+        .context("failed to collect command output")?;
+
+    let code = out.status.code().unwrap_or(2);
+    let stdout = String::from_utf8_lossy(&out.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&out.stderr).to_string();
+    Ok((code, stdout, stderr))
+}
+
+/// Like [`run_timeout`], but writes `input` to the child's stdin.
+pub async fn run_with_input(
+    program: &str,
+    args: &[&str],
+    input: &[u8],
+    timeout_ms: u64,
+) -> Result<(i32, String, String)> {
+    let started = Instant::now();
+    let res = run_child_with_input(program, args, input, timeout_ms).await;
+    record(program, started, &res);
+    res
+}
+
+async fn run_child_with_input(
+    program: &str,
+    args: &[&str],
+    input: &[u8],
+    timeout_ms: u64,
+) -> Result<(i32, String, String)> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("failed to spawn: {program}"))?;
+
+    let mut stdin = child
+        .stdin
+        .take()
+        .context("failed to capture child stdin")?;
+    // feed stdin while reading the output: a child that writes more than the
+    // pipe buffer before it has read all of its input would otherwise block
+    // both sides until the timeout
+    let feed = async move {
+        // a child that exits without reading its input is not an error here
+        let _ = stdin.write_all(input).await;
+    };
+    let run = async {
+        let ((), out) = tokio::join!(feed, child.wait_with_output());
+        out.context("failed to collect command output")
+    };
+    let out = time::timeout(Duration::from_millis(timeout_ms), run)
+        .await
+        .map_err(|_| anyhow::anyhow!("command timed out: {program} {args:?}"))??;
+
+    let code = out.status.code().unwrap_or(2);
+    let stdout = String::from_utf8_lossy(&out.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&out.stderr).to_string();
+    Ok((code, stdout, stderr))
+}
+
+/// Stdout of a long-running child process, line by line. The child is killed
+/// when the stream is dropped.
+pub struct LineStream {
+    _child: Child,
+    lines: Lines<BufReader<ChildStdout>>,
+}
+
+impl LineStream {
+    /// Next output line, or `None` once the process has exited.
+    pub async fn next_line(&mut self) -> Option<String> {
+        self.lines.next_line().await.ok().flatten()
+    }
+}
+
+pub fn spawn_lines(program: &str, args: &[&str]) -> Result<LineStream> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("failed to spawn: {program}"))?;
+    let stdout = child
+        .stdout
+        .take()
+        .context("failed to capture child stdout")?;
+    Ok(LineStream {
+        _child: child,
+        lines: BufReader::new(stdout).lines(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn collects_output_larger_than_the_pipe_buffer() {
+        let (code, out, _) = run_timeout(
+            "sh",
+            &["-c", "head -c 2097152 /dev/zero | tr '\\0' x"],
+            5000,
+        )
+        .await
+        .unwrap();
+        assert_eq!(code, 0);
+        assert_eq!(out.len(), 2 * 1024 * 1024);
+    }
+
+    #[tokio::test]
+    async fn times_out() {
+        let err = run_timeout("sleep", &["5"], 100).await.unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+    }
+
+    #[tokio::test]
+    async fn reports_exit_code_and_stderr() {
+        let (code, _, err) = run_timeout("sh", &["-c", "echo oops >&2; exit 3"], 5000)
+            .await
+            .unwrap();
+        assert_eq!(code, 3);
+        assert_eq!(err, "oops\n");
+    }
+
+    #[tokio::test]
+    async fn writes_input_while_reading_output() {
+        // the child fills its stdout pipe before reading any input
+        let input = vec![b'y'; 1024 * 1024];
+        let (code, out, _) = run_with_input(
+            "sh",
+            &["-c", "head -c 1048576 /dev/zero | tr '\\0' x; wc -c"],
+            &input,
+            5000,
+        )
+        .await
+        .unwrap();
+        assert_eq!(code, 0);
+        assert_eq!(out.len(), 1024 * 1024 + "1048576\n".len());
+        assert!(out.ends_with("1048576\n"));
     }
 }
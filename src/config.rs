@@ -1,21 +1,136 @@
 use anyhow::{Context, Result};
 use directories::ProjectDirs;
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::os::unix::fs::DirBuilderExt;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, Deserialize)]
+use crate::{network::NetworkContext, status::Overall};
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct Policy {
+    /// 1 or 2; schema 2 files are converted to the schema 1 layout on load
+    /// (see [`crate::policy::v2`]).
     pub schema: u32,
     pub backend: Option<String>,
+    /// Evaluate several backends in one run, e.g. `["firewalld", "nftables"]`
+    /// to check firewalld's zones and the nft ruleset it generates.
+    pub backends: Option<Vec<String>>,
     pub checks: Option<Checks>,
+    /// Named variants of `[checks]`, selected with `--profile` or by their
+    /// `[profiles.<name>.match]` rules; the top-level checks are `default`.
+    pub profiles: Option<BTreeMap<String, Profile>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// Parent profile whose checks this one refines (default: `default`).
+    pub extends: Option<String>,
+    /// Order of automatic selection: profiles are tried by descending
+    /// priority (default 0), then by name.
+    pub priority: Option<i64>,
+    #[serde(rename = "match")]
+    pub match_rules: Option<ProfileMatch>,
+    /// Keys merged over the parent's checks; tables merge, other values replace.
+    #[schemars(with = "Option<Checks>")]
+    pub checks: Option<toml::Table>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// Automatic selection: the profile applies when any listed value is
+/// currently active.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileMatch {
+    /// firewalld zones with an interface or source bound to them.
+    pub zones: Option<Vec<String>>,
+    /// SSIDs of connected Wi-Fi networks.
+    pub ssids: Option<Vec<String>>,
+    /// Active NetworkManager connection names.
+    pub connections: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct Checks {
     pub require_firewall_active: Option<bool>,
     pub required_services: Option<Vec<String>>,
     pub required_ports: Option<Vec<String>>,
     pub required_fragments: Option<Vec<String>>,
+    pub forbidden_services: Option<Vec<String>>,
+    pub forbidden_ports: Option<Vec<String>>,
+    pub forbidden_fragments: Option<Vec<String>>,
+    pub zones: Option<Vec<ZoneChecks>>,
+    /// Compare the live ruleset against the saved baseline (`ruleset-drift`).
+    pub verify_baseline: Option<bool>,
+    pub nftables: Option<NftChecks>,
+    pub iptables: Option<IptChecks>,
+    pub ufw: Option<UfwChecks>,
+    /// Severity overrides keyed by finding id (`missing-service:ssh`) or by
+    /// finding kind, i.e. the id up to the first `:` (`missing-service`).
+    pub severity: Option<BTreeMap<String, Overall>>,
+}
+
+/// firewalld checks scoped to a zone other than the default one. The zone is
+/// either named directly or resolved from the interface bound to it.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ZoneChecks {
+    pub zone: Option<String>,
+    pub interface: Option<String>,
+    pub required_services: Option<Vec<String>>,
+    pub required_ports: Option<Vec<String>>,
+    pub forbidden_services: Option<Vec<String>>,
+    pub forbidden_ports: Option<Vec<String>>,
+}
+
+/// Structural assertions evaluated against the parsed nftables ruleset.
+/// Tables are addressed as `"<family> <name>"`, e.g. `"inet filter"`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NftChecks {
+    pub tables: Option<Vec<String>>,
+    pub chains: Option<Vec<NftChainCheck>>,
+    pub rules: Option<Vec<NftRuleCheck>>,
+    /// Rules that must not exist (any chain position).
+    pub forbidden_rules: Option<Vec<NftRuleCheck>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NftChainCheck {
+    pub table: String,
+    /// Chain name; when omitted the base chain registered on `hook` is used.
+    pub chain: Option<String>,
+    pub hook: Option<String>,
+    pub priority: Option<i64>,
+    pub policy: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NftRuleCheck {
+    pub table: String,
+    pub chain: String,
+    /// `tcp`, `udp`, ...
+    pub protocol: String,
+    pub dport: u16,
+    /// Expected verdict of the matching rule (default: `accept`).
+    pub verdict: Option<String>,
+    /// Require the rule to precede any drop/reject that would also match it.
+    pub before_drop: Option<bool>,
+}
+
+/// Expected ufw default policies (`allow`, `deny`, `reject`, `disabled`),
+/// e.g. `default_incoming = "deny"`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UfwChecks {
+    pub default_incoming: Option<String>,
+    pub default_outgoing: Option<String>,
+    pub default_routed: Option<String>,
 }
 
 impl Default for Policy {
@@ -23,6 +138,8 @@ impl Default for Policy {
         Self {
             schema: 1,
             backend: Some("auto".into()),
+            backends: None,
+            profiles: None,
             checks: Some(Checks::default()),
         }
     }
@@ -35,29 +152,210 @@ impl Default for Checks {
             required_services: None,
             required_ports: None,
             required_fragments: None,
+            forbidden_services: None,
+            forbidden_ports: None,
+            forbidden_fragments: None,
+            zones: None,
+            verify_baseline: None,
+            nftables: None,
+            iptables: None,
+            ufw: None,
+            severity: None,
         }
     }
 }
 
+/// Structural assertions evaluated against `iptables-save`/`ip6tables-save`.
+/// Unless `family` (`ipv4`/`ipv6`) is set, a check applies to every family
+/// that produced a ruleset.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct IptChecks {
+    pub chains: Option<Vec<IptChainCheck>>,
+    pub rules: Option<Vec<IptRuleCheck>>,
+    /// Rules that must not exist (any chain position).
+    pub forbidden_rules: Option<Vec<IptRuleCheck>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct IptChainCheck {
+    /// Defaults to `filter`.
+    pub table: Option<String>,
+    pub chain: String,
+    pub policy: Option<String>,
+    pub family: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct IptRuleCheck {
+    /// Defaults to `filter`.
+    pub table: Option<String>,
+    pub chain: String,
+    pub protocol: String,
+    pub dport: u16,
+    /// Expected target of the matching rule (default: `ACCEPT`).
+    pub target: Option<String>,
+    /// Require the rule to precede any DROP/REJECT that would also match it.
+    pub before_drop: Option<bool>,
+    pub family: Option<String>,
+}
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+impl Policy {
+    /// The policy with `name`'s checks merged over its ancestors' and the
+    /// top-level `[checks]`.
+    pub fn with_profile(&self, name: &str) -> Result<Policy> {
+        let profiles = self.profiles.clone().unwrap_or_default();
+        let mut chain = Vec::new();
+        let mut current = name;
+        while current != DEFAULT_PROFILE || profiles.contains_key(current) {
+            let Some(profile) = profiles.get(current) else {
+                anyhow::bail!("unknown profile: {current}");
+            };
+            anyhow::ensure!(
+                !chain.iter().any(|(n, _)| *n == current),
+                "profile inheritance cycle at: {current}"
+            );
+            chain.push((current, profile));
+            match profile.extends.as_deref() {
+                Some(parent) => current = parent,
+                None => break,
+            }
+        }
+
+        let mut merged = toml::Value::try_from(self.checks.clone().unwrap_or_default())
+            .context("failed to serialize base checks")?;
+        for (_, profile) in chain.iter().rev() {
+            if let Some(checks) = &profile.checks {
+                merge_toml(&mut merged, toml::Value::Table(checks.clone()));
+            }
+        }
+        let checks: Checks = merged
+            .try_into()
+            .with_context(|| format!("invalid checks in profile {name}"))?;
+
+        Ok(Policy {
+            checks: Some(checks),
+            ..self.clone()
+        })
+    }
+
+    /// First profile whose match rules hit the current network, trying
+    /// profiles in [`Policy::selection_order`].
+    pub fn matching_profile(&self, net: &NetworkContext) -> Option<&str> {
+        let hit = |wanted: &Option<Vec<String>>, active: &[String]| {
+            wanted
+                .iter()
+                .flatten()
+                .any(|w| active.iter().any(|a| a == w))
+        };
+        self.selection_order().into_iter().find_map(|(name, p)| {
+            let m = p.match_rules.as_ref()?;
+            (hit(&m.zones, &net.zones)
+                || hit(&m.ssids, &net.ssids)
+                || hit(&m.connections, &net.connections))
+            .then_some(name)
+        })
+    }
+
+    /// Profiles by descending `priority`, ties broken by name.
+    pub fn selection_order(&self) -> Vec<(&str, &Profile)> {
+        let mut order: Vec<(&str, &Profile)> = self
+            .profiles
+            .iter()
+            .flatten()
+            .map(|(name, p)| (name.as_str(), p))
+            .collect();
+        order.sort_by_key(|(_, p)| std::cmp::Reverse(p.priority.unwrap_or(0)));
+        order
+    }
+
+    pub fn has_match_rules(&self) -> bool {
+        self.profiles
+            .iter()
+            .flatten()
+            .any(|(_, p)| p.match_rules.is_some())
+    }
+}
+
+/// Deep-merge `overlay` into `base`: tables merge key by key, anything else
+/// (including arrays) is replaced.
+fn merge_toml(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+impl Checks {
+    /// Configured severity for a finding: an exact id match wins over a kind match.
+    pub fn severity_for(&self, id: &str) -> Option<Overall> {
+        let map = self.severity.as_ref()?;
+        map.get(id).copied().or_else(|| {
+            id.split_once(':')
+                .and_then(|(kind, _)| map.get(kind))
+                .copied()
+        })
+    }
+}
+
+/// Split a `"<port>/<proto>"` spec such as `"3389/tcp"`.
+pub fn parse_port_spec(spec: &str) -> Option<(u16, &str)> {
+    let (port, proto) = spec.split_once('/')?;
+    let port = port.trim().parse().ok()?;
+    let proto = proto.trim();
+    (!proto.is_empty()).then_some((port, proto))
+}
+
 pub fn default_policy_path() -> Result<PathBuf> {
     let proj = ProjectDirs::from("org", "sentinel", "sentinel")
         .context("unable to determine XDG project dirs")?;
     Ok(proj.config_dir().join("policy.toml"))
 }
 
-pub fn load_policy(path: &Path) -> Result<Policy> {
-    let default_path = default_policy_path()?;
-    if !path.exists() {
-        if path == default_path {
-            tracing::warn!(path = %path.display(), "policy file not found; using default inline policy");
-            return Ok(Policy::default());
-        }
-        anyhow::bail!("policy file not found: {}", path.display());
-    }
+pub fn default_state_dir() -> Result<PathBuf> {
+    let proj = ProjectDirs::from("org", "sentinel", "sentinel")
+        .context("unable to determine XDG project dirs")?;
+    let dir = proj
+        .state_dir()
+        .context("unable to determine XDG state dir")?;
+    Ok(dir.to_path_buf())
+}
 
-    let s = std::fs::read_to_string(path)
-        .with_context(|| format!("failed to read policy file: {}", path.display()))?;
-    let p: Policy = toml::from_str(&s).context("failed to parse policy TOML")?;
-    anyhow::ensure!(p.schema == 1, "unsupported policy schema: {}", p.schema);
+/// The state dir, created with mode 0700 if missing.
+pub fn ensure_state_dir() -> Result<PathBuf> {
+    let dir = default_state_dir()?;
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)
+        .with_context(|| format!("failed to create state dir: {}", dir.display()))?;
+    Ok(dir)
+}
+
+/// Load the policy at `path` merged with its includes and drop-ins; see
+/// [`crate::policy::resolve`].
+pub fn load_policy(path: &Path) -> Result<Policy> {
+    let p = crate::policy::resolve(path)
+        .and_then(|r| r.policy())
+        .map_err(|err| {
+            // prefer the first located problem over serde's merged-table error
+            match crate::policy::lint::lint(path).into_iter().next() {
+                Some(problem) => anyhow::anyhow!("{problem}"),
+                None => err,
+            }
+        })?;
     Ok(p)
 }
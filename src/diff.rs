@@ -0,0 +1,254 @@
+use serde::Serialize;
+use similar::TextDiff;
+use std::io::IsTerminal;
+use std::path::Path;
+
+use crate::status::{Finding, Overall};
+
+/// One policy check whose live state deviates from what the policy expects.
+#[derive(Clone, Debug, Serialize)]
+pub struct CheckDiff {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    pub severity: Overall,
+    pub expected: String,
+    pub actual: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+}
+
+/// Unified diff of one backend's normalized live snapshot against its saved
+/// baseline; `path` is `None` when no baseline has been saved.
+#[derive(Clone, Debug, Serialize)]
+pub struct BaselineDiff {
+    pub backend: String,
+    pub path: Option<String>,
+    pub changed: bool,
+    pub unified: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DiffReport {
+    pub backend: String,
+    pub policy: Vec<CheckDiff>,
+    pub baselines: Vec<BaselineDiff>,
+}
+
+impl DiffReport {
+    pub fn has_changes(&self) -> bool {
+        !self.policy.is_empty() || self.baselines.iter().any(|b| b.changed)
+    }
+}
+
+pub fn policy_diff(findings: &[Finding]) -> Vec<CheckDiff> {
+    findings
+        .iter()
+        .filter(|f| f.severity != Overall::Ok)
+        .map(|f| CheckDiff {
+            id: f.id.clone(),
+            backend: f.backend.clone(),
+            severity: f.severity,
+            expected: f.expected.clone().unwrap_or_else(|| expectation(&f.id)),
+            actual: f.actual.clone().unwrap_or_else(|| f.msg.clone()),
+            remediation: f.remediation.clone(),
+        })
+        .collect()
+}
+
+/// Describe what the policy asked for, derived from the finding id
+/// (`<kind>:<subject>`), for findings that carry no `expected` value.
+fn expectation(id: &str) -> String {
+    let (kind, subject) = id.split_once(':').unwrap_or((id, ""));
+    // iptables findings mirror the nftables ones (`ipt-rule`, `ipt-chain-policy`, ...)
+    let kind = kind
+        .strip_prefix("nft-")
+        .or_else(|| kind.strip_prefix("ipt-"))
+        .unwrap_or(kind);
+    if kind.starts_with("forbidden-") {
+        format!("{subject} absent")
+    } else if kind.starts_with("missing-") || matches!(kind, "table" | "chain" | "rule") {
+        format!("{subject} present")
+    } else if kind == "rule-order" {
+        format!("{subject} before any matching drop/reject")
+    } else if let Some(attr) = kind.strip_prefix("chain-") {
+        format!("{subject} {attr} as configured")
+    } else {
+        "compliant".into()
+    }
+}
+
+pub fn baseline_diff(backend: &str, path: &Path, baseline: &str, current: &str) -> BaselineDiff {
+    let diff = TextDiff::from_lines(baseline, current);
+    let unified = diff
+        .unified_diff()
+        .context_radius(3)
+        .header(&path.display().to_string(), "live")
+        .to_string();
+    BaselineDiff {
+        backend: backend.to_string(),
+        path: Some(path.display().to_string()),
+        changed: baseline != current,
+        unified,
+    }
+}
+
+pub fn no_baseline(backend: &str) -> BaselineDiff {
+    BaselineDiff {
+        backend: backend.to_string(),
+        path: None,
+        changed: false,
+        unified: String::new(),
+    }
+}
+
+pub fn print_human(report: &DiffReport, color: bool) {
+    let paint = |code: &str, s: &str| {
+        if color {
+            format!("\x1b[{code}m{s}\x1b[0m")
+        } else {
+            s.to_string()
+        }
+    };
+
+    println!("policy vs live ({}):", report.backend);
+    if report.policy.is_empty() {
+        println!("  all policy checks satisfied");
+    }
+    for c in &report.policy {
+        let sev = match c.severity {
+            Overall::Error => paint("31", c.severity.as_str()),
+            Overall::Warn => paint("33", c.severity.as_str()),
+            Overall::Ok => c.severity.as_str().to_string(),
+        };
+        match c.backend.as_deref().filter(|_| report.baselines.len() > 1) {
+            Some(backend) => println!("  [{sev}] {} ({backend})", c.id),
+            None => println!("  [{sev}] {}", c.id),
+        }
+        println!("    {}", paint("32", &format!("expected: {}", c.expected)));
+        println!("    {}", paint("31", &format!("actual:   {}", c.actual)));
+        if let Some(fix) = &c.remediation {
+            println!("    fix:      {fix}");
+        }
+    }
+
+    for b in &report.baselines {
+        println!();
+        let label = if report.baselines.len() > 1 {
+            format!("baseline ({})", b.backend)
+        } else {
+            "baseline".to_string()
+        };
+        let Some(path) = &b.path else {
+            println!("{label}: none saved");
+            continue;
+        };
+        if !b.changed {
+            println!("{label}: live ruleset matches {path}");
+            continue;
+        }
+        println!("{label}: live ruleset differs from {path}");
+        for line in b.unified.lines() {
+            let colored = if line.starts_with("+++") || line.starts_with("---") {
+                paint("1", line)
+            } else if line.starts_with('+') {
+                paint("32", line)
+            } else if line.starts_with('-') {
+                paint("31", line)
+            } else if line.starts_with("@@") {
+                paint("36", line)
+            } else {
+                line.to_string()
+            };
+            println!("{colored}");
+        }
+    }
+}
+
+/// Color only when writing to a terminal and `NO_COLOR` is unset.
+pub fn use_color(no_color: bool) -> bool {
+    !no_color && std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policy_diff_lists_deviating_checks_only() {
+        let mut forbidden = Finding::new("forbidden-port:23/tcp", Overall::Error, "23/tcp is open")
+            .with_remediation("ufw delete allow 23/tcp");
+        forbidden.backend = Some("ufw".into());
+        let findings = [
+            Finding::pass("missing-port:22/tcp", "22/tcp is open"),
+            forbidden,
+            Finding::new(
+                "nft-chain-policy:inet filter/input",
+                Overall::Warn,
+                "policy is accept",
+            )
+            .with_values("drop", "accept"),
+            Finding::new(
+                "ipt-rule:filter/INPUT/-p tcp --dport 22 -j ACCEPT",
+                Overall::Error,
+                "rule not found",
+            ),
+            Finding::new(
+                "rule-order:inet filter/input/tcp dport 22 accept",
+                Overall::Warn,
+                "rule is shadowed",
+            ),
+            Finding::new("ufw-active", Overall::Error, "ufw is inactive"),
+        ];
+        let diff = policy_diff(&findings);
+        let got: Vec<_> = diff
+            .iter()
+            .map(|d| (d.id.as_str(), d.expected.as_str(), d.actual.as_str()))
+            .collect();
+        assert_eq!(
+            got,
+            [
+                ("forbidden-port:23/tcp", "23/tcp absent", "23/tcp is open"),
+                ("nft-chain-policy:inet filter/input", "drop", "accept"),
+                (
+                    "ipt-rule:filter/INPUT/-p tcp --dport 22 -j ACCEPT",
+                    "filter/INPUT/-p tcp --dport 22 -j ACCEPT present",
+                    "rule not found"
+                ),
+                (
+                    "rule-order:inet filter/input/tcp dport 22 accept",
+                    "inet filter/input/tcp dport 22 accept before any matching drop/reject",
+                    "rule is shadowed"
+                ),
+                ("ufw-active", "compliant", "ufw is inactive"),
+            ]
+        );
+        assert_eq!(diff[0].backend.as_deref(), Some("ufw"));
+        assert_eq!(
+            diff[0].remediation.as_deref(),
+            Some("ufw delete allow 23/tcp")
+        );
+        assert_eq!(diff[1].severity, Overall::Warn);
+    }
+
+    #[test]
+    fn baseline_diff_is_unified() {
+        let path = Path::new("/var/lib/sentinel/baseline/ufw.txt");
+        let diff = baseline_diff(
+            "ufw",
+            path,
+            "22/tcp ALLOW\n80/tcp ALLOW\n",
+            "22/tcp ALLOW\n23/tcp ALLOW\n",
+        );
+        assert!(diff.changed);
+        assert_eq!(
+            diff.unified,
+            "--- /var/lib/sentinel/baseline/ufw.txt\n+++ live\n@@ -1,2 +1,2 @@\n 22/tcp ALLOW\n-80/tcp ALLOW\n+23/tcp ALLOW\n"
+        );
+
+        let same = baseline_diff("ufw", path, "22/tcp ALLOW\n", "22/tcp ALLOW\n");
+        assert!(!same.changed);
+        assert!(same.unified.is_empty());
+        assert!(!no_baseline("ufw").changed);
+    }
+}
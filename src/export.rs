@@ -0,0 +1,328 @@
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use crate::status::{CheckStatus, Finding, Overall, StatusReport};
+
+/// JUnit XML: one `<testsuite>` per backend and one `<testcase>` per check,
+/// named by finding id. Warn and error findings become `<failure>`s whose
+/// `type` is the severity and whose body carries the expected/actual values
+/// and remediation; skipped checks become `<skipped>`, passed checks are plain
+/// test cases.
+pub fn junit(report: &StatusReport) -> String {
+    let mut suites: BTreeMap<&str, Vec<&Finding>> = BTreeMap::new();
+    for f in &report.findings {
+        suites.entry(backend(report, f)).or_default().push(f);
+    }
+    let count = |findings: &[&Finding], status: &[CheckStatus]| {
+        findings
+            .iter()
+            .filter(|f| status.contains(&f.status))
+            .count()
+    };
+    let failures = |findings: &[&Finding]| count(findings, &[CheckStatus::Warn, CheckStatus::Fail]);
+    let skipped = |findings: &[&Finding]| count(findings, &[CheckStatus::Skipped]);
+    let timestamp = report.last_check.to_rfc3339();
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let all: Vec<&Finding> = report.findings.iter().collect();
+    let _ = writeln!(
+        out,
+        r#"<testsuites name="sentinel" tests="{}" failures="{}" errors="0" skipped="{}" timestamp="{timestamp}">"#,
+        all.len(),
+        failures(&all),
+        skipped(&all),
+    );
+    for (backend, findings) in &suites {
+        let _ = writeln!(
+            out,
+            r#"  <testsuite name="sentinel.{}" tests="{}" failures="{}" errors="0" skipped="{}" timestamp="{timestamp}">"#,
+            escape(backend),
+            findings.len(),
+            failures(findings),
+            skipped(findings),
+        );
+        let _ = writeln!(out, "    <properties>");
+        for (name, value) in [
+            ("profile", report.active_profile.as_str()),
+            ("overall", report.overall.as_str()),
+        ] {
+            let _ = writeln!(
+                out,
+                r#"      <property name="{name}" value="{}"/>"#,
+                escape(value)
+            );
+        }
+        let _ = writeln!(out, "    </properties>");
+        for f in findings {
+            let head = format!(
+                r#"    <testcase name="{}" classname="sentinel.{}""#,
+                escape(&f.id),
+                escape(backend)
+            );
+            let msg = escape(&f.msg);
+            match f.status {
+                CheckStatus::Pass => {
+                    let _ = writeln!(out, "{head}/>");
+                }
+                CheckStatus::Skipped => {
+                    let _ = writeln!(out, "{head}>");
+                    let _ = writeln!(out, r#"      <skipped message="{msg}"/>"#);
+                    let _ = writeln!(out, "    </testcase>");
+                }
+                CheckStatus::Warn | CheckStatus::Fail => {
+                    let mut body = format!("[{}] {}: {}", f.severity.as_str(), f.id, f.msg);
+                    for (label, value) in [
+                        ("expected", &f.expected),
+                        ("actual", &f.actual),
+                        ("fix", &f.remediation),
+                    ] {
+                        if let Some(value) = value {
+                            let _ = write!(body, "\n{label}: {value}");
+                        }
+                    }
+                    let _ = writeln!(out, "{head}>");
+                    let _ = writeln!(
+                        out,
+                        r#"      <failure type="{}" message="{msg}">{}</failure>"#,
+                        f.severity.as_str(),
+                        escape(&body),
+                    );
+                    let _ = writeln!(out, "    </testcase>");
+                }
+            }
+        }
+        let _ = writeln!(out, "  </testsuite>");
+    }
+    out.push_str("</testsuites>\n");
+    out
+}
+
+/// SARIF 2.1.0 log with one result per check. Rules are the finding kinds
+/// (the id up to the first `:`); passed checks are `kind: "pass"` results and
+/// skipped ones `kind: "notApplicable"`. Evidence, expected/actual values and
+/// remediation go into the result's property bag.
+pub fn sarif(report: &StatusReport) -> Value {
+    let mut rules = BTreeMap::new();
+    let results: Vec<Value> = report
+        .findings
+        .iter()
+        .map(|f| {
+            let rule = rule_id(&f.id);
+            rules
+                .entry(rule)
+                .or_insert_with(|| json!({ "id": rule, "name": rule }));
+            let (kind, level) = match (f.status, f.severity) {
+                (CheckStatus::Skipped, _) => ("notApplicable", "none"),
+                (_, Overall::Ok) => ("pass", "none"),
+                (_, Overall::Warn) => ("fail", "warning"),
+                (_, Overall::Error) => ("fail", "error"),
+            };
+            let backend = backend(report, f);
+            let mut properties = json!({ "backend": backend });
+            for (key, value) in [
+                ("expected", &f.expected),
+                ("actual", &f.actual),
+                ("remediation", &f.remediation),
+            ] {
+                if let Some(value) = value {
+                    properties[key] = json!(value);
+                }
+            }
+            if let Some(evidence) = &f.evidence {
+                properties["evidence"] = json!(evidence);
+            }
+            json!({
+                "ruleId": rule,
+                "kind": kind,
+                "level": level,
+                "message": { "text": f.msg },
+                "locations": [{
+                    "logicalLocations": [{
+                        "name": f.id,
+                        "fullyQualifiedName": format!("{backend}/{}", f.id),
+                        "kind": "resource",
+                    }],
+                }],
+                "partialFingerprints": { "findingId/v1": format!("{backend}/{}", f.id) },
+                "properties": properties,
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "sentinel",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.into_values().collect::<Vec<_>>(),
+                },
+            },
+            "invocations": [{
+                "executionSuccessful": true,
+                "endTimeUtc": report.last_check.to_rfc3339(),
+            }],
+            "results": results,
+            "properties": {
+                "backend": report.backend,
+                "profile": report.active_profile,
+                "overall": report.overall,
+            },
+        }],
+    })
+}
+
+fn backend<'a>(report: &'a StatusReport, f: &'a Finding) -> &'a str {
+    f.backend.as_deref().unwrap_or(&report.backend)
+}
+
+/// Finding kind, e.g. `missing-port` for `missing-port:22/tcp`.
+fn rule_id(id: &str) -> &str {
+    id.split_once(':').map_or(id, |(kind, _)| kind)
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\n' => out.push_str("&#10;"),
+            // not allowed in XML 1.0
+            c if c.is_control() && c != '\t' && c != '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::{Evidence, Summary};
+    use chrono::{TimeZone, Utc};
+
+    fn report() -> StatusReport {
+        let mut open = Finding::new(
+            "forbidden-port:23/tcp",
+            Overall::Error,
+            "23/tcp <telnet> is open",
+        )
+        .with_evidence(Evidence::rule("23/tcp").with_snippet("tcp dport 23 accept"))
+        .with_values("closed", "open")
+        .with_remediation("nft delete rule inet filter input handle 7");
+        open.backend = Some("nftables".into());
+        let mut ssh = Finding::pass("missing-port:22/tcp", "22/tcp is open");
+        ssh.backend = Some("ufw".into());
+        let mut skipped = Finding::skipped("missing-service:ssh", "no services on nftables");
+        skipped.backend = Some("nftables".into());
+        StatusReport {
+            schema: crate::status::REPORT_SCHEMA,
+            overall: Overall::Error,
+            backend: "ufw+nftables".into(),
+            active_profile: "office".into(),
+            last_check: Utc.with_ymd_and_hms(2026, 10, 1, 12, 0, 0).unwrap(),
+            summary: Summary {
+                checks_total: 3,
+                checks_passed: 1,
+                checks_warn: 0,
+                checks_failed: 1,
+                checks_skipped: 1,
+            },
+            findings: vec![ssh, open, skipped],
+        }
+    }
+
+    #[test]
+    fn junit_has_a_suite_per_backend() {
+        assert_eq!(
+            junit(&report()),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="sentinel" tests="3" failures="1" errors="0" skipped="1" timestamp="2026-10-01T12:00:00+00:00">
+  <testsuite name="sentinel.nftables" tests="2" failures="1" errors="0" skipped="1" timestamp="2026-10-01T12:00:00+00:00">
+    <properties>
+      <property name="profile" value="office"/>
+      <property name="overall" value="error"/>
+    </properties>
+    <testcase name="forbidden-port:23/tcp" classname="sentinel.nftables">
+      <failure type="error" message="23/tcp &lt;telnet&gt; is open">[error] forbidden-port:23/tcp: 23/tcp &lt;telnet&gt; is open&#10;expected: closed&#10;actual: open&#10;fix: nft delete rule inet filter input handle 7</failure>
+    </testcase>
+    <testcase name="missing-service:ssh" classname="sentinel.nftables">
+      <skipped message="no services on nftables"/>
+    </testcase>
+  </testsuite>
+  <testsuite name="sentinel.ufw" tests="1" failures="0" errors="0" skipped="0" timestamp="2026-10-01T12:00:00+00:00">
+    <properties>
+      <property name="profile" value="office"/>
+      <property name="overall" value="error"/>
+    </properties>
+    <testcase name="missing-port:22/tcp" classname="sentinel.ufw"/>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+
+    #[test]
+    fn sarif_has_a_result_per_check() {
+        let log = sarif(&report());
+        let run = &log["runs"][0];
+        assert_eq!(log["version"], "2.1.0");
+        assert_eq!(
+            run["tool"]["driver"]["rules"],
+            json!([
+                { "id": "forbidden-port", "name": "forbidden-port" },
+                { "id": "missing-port", "name": "missing-port" },
+                { "id": "missing-service", "name": "missing-service" },
+            ])
+        );
+        let results: Vec<_> = run["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| {
+                (
+                    r["ruleId"].as_str().unwrap(),
+                    r["kind"].as_str().unwrap(),
+                    r["level"].as_str().unwrap(),
+                    r["partialFingerprints"]["findingId/v1"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            results,
+            [
+                ("missing-port", "pass", "none", "ufw/missing-port:22/tcp"),
+                (
+                    "forbidden-port",
+                    "fail",
+                    "error",
+                    "nftables/forbidden-port:23/tcp"
+                ),
+                (
+                    "missing-service",
+                    "notApplicable",
+                    "none",
+                    "nftables/missing-service:ssh"
+                ),
+            ]
+        );
+        assert_eq!(
+            run["results"][1]["properties"],
+            json!({
+                "backend": "nftables",
+                "expected": "closed",
+                "actual": "open",
+                "remediation": "nft delete rule inet filter input handle 7",
+                "evidence": { "rule": "23/tcp", "snippet": "tcp dport 23 accept" },
+            })
+        );
+        assert_eq!(run["properties"]["overall"], "error");
+    }
+}
@@ -0,0 +1,344 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::{
+    config::{default_state_dir, ensure_state_dir},
+    status::{Overall, StatusReport, Summary},
+    statusfile,
+};
+
+/// Entries kept in `history.jsonl`; older ones are dropped on rewrite.
+const MAX_ENTRIES: usize = 1000;
+
+/// Compact record of a check run whose state differed from the previous one,
+/// one JSON object per line.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub ts: DateTime<Utc>,
+    pub backend: String,
+    pub overall: Overall,
+    pub summary: Summary,
+    pub findings: Vec<EntryFinding>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EntryFinding {
+    pub id: String,
+    /// Backend that reported the finding; entries written before findings
+    /// were tagged leave it out, and their findings belong to the entry's
+    /// backend.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    pub severity: Overall,
+}
+
+impl EntryFinding {
+    fn backend<'a>(&'a self, entry: &'a Entry) -> &'a str {
+        self.backend.as_deref().unwrap_or(&entry.backend)
+    }
+}
+
+impl Entry {
+    /// True if the run evaluated `backend` (the label may join several,
+    /// e.g. `firewalld+nftables`).
+    fn covers(&self, backend: &str) -> bool {
+        self.backend == backend || self.backend.split('+').any(|b| b == backend)
+    }
+}
+
+/// A change of the overall state between two consecutive entries.
+#[derive(Clone, Debug, Serialize)]
+pub struct Transition {
+    pub ts: DateTime<Utc>,
+    pub backend: String,
+    pub from: Option<Overall>,
+    pub to: Overall,
+    pub summary: Summary,
+}
+
+pub fn history_path() -> Result<PathBuf> {
+    Ok(default_state_dir()?.join("history.jsonl"))
+}
+
+pub fn load() -> Result<Vec<Entry>> {
+    let path = history_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let s = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read history: {}", path.display()))?;
+    Ok(s.lines()
+        .filter(|l| !l.trim().is_empty())
+        .filter_map(|l| match serde_json::from_str(l) {
+            Ok(e) => Some(e),
+            Err(err) => {
+                debug!(error = ?err, "skipping malformed history line");
+                None
+            }
+        })
+        .collect())
+}
+
+/// Fill `first_seen`/`since` on every failed check from the previous runs
+/// of the backend that reported it; findings are keyed by `(backend, id)`.
+pub fn annotate(report: &mut StatusReport, history: &[Entry]) {
+    let now = report.last_check;
+    let label = report.backend.clone();
+    for f in report
+        .findings
+        .iter_mut()
+        .filter(|f| f.severity != Overall::Ok)
+    {
+        let backend = f.backend.as_deref().unwrap_or(&label);
+        let runs: Vec<&Entry> = history.iter().filter(|e| e.covers(backend)).collect();
+        let seen_in = |e: &Entry| {
+            e.findings
+                .iter()
+                .any(|h| h.id == f.id && h.backend(e) == backend)
+        };
+
+        let since = runs
+            .iter()
+            .rev()
+            .take_while(|e| seen_in(e))
+            .last()
+            .map(|e| e.ts)
+            .unwrap_or(now);
+        let first_seen = runs
+            .iter()
+            .find(|e| seen_in(e))
+            .map(|e| e.ts)
+            .unwrap_or(now);
+
+        f.first_seen = Some(first_seen.min(since));
+        f.since = Some(since);
+    }
+}
+
+/// Append the report to the history when its state (overall + findings)
+/// differs from the last entry for the same backend, trimming the file once
+/// it grows past the cap. The history is re-read under a lock, so concurrent
+/// checks (`watch` next to `nm-dispatch`) cannot drop each other's entries.
+pub fn record(report: &StatusReport) -> Result<()> {
+    let entry = Entry {
+        ts: report.last_check,
+        backend: report.backend.clone(),
+        overall: report.overall,
+        summary: report.summary.clone(),
+        findings: report
+            .findings
+            .iter()
+            .filter(|f| f.severity != Overall::Ok)
+            .map(|f| EntryFinding {
+                id: f.id.clone(),
+                backend: f.backend.clone(),
+                severity: f.severity,
+            })
+            .collect(),
+    };
+
+    let dir = ensure_state_dir()?;
+    let _lock = lock(&dir)?;
+    let history = load()?;
+    let previous = history.iter().rev().find(|e| e.backend == entry.backend);
+    if previous.is_some_and(|last| same_state(last, &entry)) {
+        return Ok(());
+    }
+
+    let path = dir.join("history.jsonl");
+
+    if history.len() >= MAX_ENTRIES + MAX_ENTRIES / 10 {
+        let keep = &history[history.len() + 1 - MAX_ENTRIES..];
+        let mut out = String::new();
+        for e in keep.iter().chain(std::iter::once(&entry)) {
+            out.push_str(&serde_json::to_string(e)?);
+            out.push('\n');
+        }
+        return statusfile::write_atomic(&path, out.as_bytes(), 0o600);
+    }
+
+    let mut f = std::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .mode(0o600)
+        .open(&path)
+        .with_context(|| format!("failed to open history: {}", path.display()))?;
+    let mut line = serde_json::to_string(&entry)?;
+    line.push('\n');
+    f.write_all(line.as_bytes())
+        .with_context(|| format!("failed to append history: {}", path.display()))?;
+    Ok(())
+}
+
+/// Exclusive lock on `<state>/history.lock`, released when dropped. A
+/// separate file, since trimming replaces `history.jsonl` itself.
+fn lock(dir: &Path) -> Result<File> {
+    let path = dir.join("history.lock");
+    let f = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .mode(0o600)
+        .open(&path)
+        .with_context(|| format!("failed to open history lock: {}", path.display()))?;
+    f.lock()
+        .with_context(|| format!("failed to lock history: {}", path.display()))?;
+    Ok(f)
+}
+
+fn same_state(a: &Entry, b: &Entry) -> bool {
+    a.backend == b.backend
+        && a.overall == b.overall
+        && a.findings.len() == b.findings.len()
+        && a.findings
+            .iter()
+            .zip(&b.findings)
+            .all(|(x, y)| x.id == y.id && x.backend == y.backend && x.severity == y.severity)
+}
+
+/// Changes of the overall state, tracked per backend label.
+pub fn transitions(history: &[Entry]) -> Vec<Transition> {
+    let mut out = Vec::new();
+    let mut prev: HashMap<&str, Overall> = HashMap::new();
+    for e in history {
+        let from = prev.insert(&e.backend, e.overall);
+        if from != Some(e.overall) {
+            out.push(Transition {
+                ts: e.ts,
+                backend: e.backend.clone(),
+                from,
+                to: e.overall,
+                summary: e.summary.clone(),
+            });
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::Finding;
+    use chrono::{TimeZone, Timelike};
+
+    fn ts(minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 1, 12, minute, 0).unwrap()
+    }
+
+    fn entry(minute: u32, backend: &str, findings: &[(&str, Option<&str>)]) -> Entry {
+        Entry {
+            ts: ts(minute),
+            backend: backend.into(),
+            overall: if findings.is_empty() {
+                Overall::Ok
+            } else {
+                Overall::Error
+            },
+            summary: Summary {
+                checks_total: 0,
+                checks_passed: 0,
+                checks_warn: 0,
+                checks_failed: 0,
+                checks_skipped: 0,
+            },
+            findings: findings
+                .iter()
+                .map(|(id, backend)| EntryFinding {
+                    id: id.to_string(),
+                    backend: backend.map(String::from),
+                    severity: Overall::Error,
+                })
+                .collect(),
+        }
+    }
+
+    fn report(minute: u32, backend: &str, findings: &[(&str, &str)]) -> StatusReport {
+        let e = entry(minute, backend, &[]);
+        StatusReport {
+            schema: crate::status::REPORT_SCHEMA,
+            overall: Overall::Error,
+            backend: e.backend,
+            active_profile: "default".into(),
+            last_check: e.ts,
+            summary: e.summary,
+            findings: findings
+                .iter()
+                .map(|(id, backend)| {
+                    let mut f = Finding::new(*id, Overall::Error, "failed");
+                    f.backend = Some(backend.to_string());
+                    f
+                })
+                .collect(),
+        }
+    }
+
+    const PORT: &str = "forbidden-port:23/tcp";
+
+    #[test]
+    fn annotate_keys_findings_by_backend() {
+        let history = [
+            entry(1, "firewalld+nftables", &[(PORT, Some("nftables"))]),
+            entry(2, "firewalld+nftables", &[(PORT, Some("firewalld"))]),
+        ];
+        let mut now = report(
+            3,
+            "firewalld+nftables",
+            &[(PORT, "firewalld"), (PORT, "nftables")],
+        );
+        annotate(&mut now, &history);
+
+        let firewalld = &now.findings[0];
+        assert_eq!(
+            (firewalld.first_seen, firewalld.since),
+            (Some(ts(2)), Some(ts(2)))
+        );
+        // nftables did not report it at 12:02, so its current run starts now
+        let nftables = &now.findings[1];
+        assert_eq!(
+            (nftables.first_seen, nftables.since),
+            (Some(ts(1)), Some(ts(3)))
+        );
+    }
+
+    #[test]
+    fn runs_of_other_backends_do_not_interrupt() {
+        let history = [
+            // written before findings carried their backend
+            entry(1, "nftables", &[(PORT, None)]),
+            entry(2, "ufw", &[]),
+            entry(3, "nftables", &[(PORT, Some("nftables"))]),
+        ];
+        let mut now = report(4, "nftables", &[(PORT, "nftables")]);
+        annotate(&mut now, &history);
+        assert_eq!(now.findings[0].since, Some(ts(1)));
+    }
+
+    #[test]
+    fn transitions_are_tracked_per_backend() {
+        let history = [
+            entry(1, "nftables", &[(PORT, None)]),
+            entry(2, "ufw", &[]),
+            entry(3, "nftables", &[(PORT, None)]),
+            entry(4, "nftables", &[]),
+        ];
+        let changes: Vec<(u32, Option<Overall>, Overall)> = transitions(&history)
+            .iter()
+            .map(|t| (t.ts.minute(), t.from, t.to))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (1, None, Overall::Error),
+                (2, None, Overall::Ok),
+                (4, Some(Overall::Error), Overall::Ok),
+            ]
+        );
+    }
+}
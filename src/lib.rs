@@ -1,6 +1,15 @@
 pub mod app;
 pub mod backends;
+pub mod baseline;
 pub mod cli;
 pub mod cmd;
 pub mod config;
+pub mod diff;
+pub mod export;
+pub mod history;
+pub mod metrics;
+pub mod network;
+pub mod policy;
+pub mod schema;
 pub mod status;
+pub mod statusfile;
@@ -0,0 +1,348 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::time::Duration;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time,
+};
+
+use crate::{
+    cmd::CommandStats,
+    status::{CheckStatus, Overall, StatusReport},
+};
+
+/// Prometheus text exposition format (also what node_exporter's textfile
+/// collector reads).
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Render the report, backend detection results and command statistics as
+/// Prometheus metrics.
+pub fn render(
+    report: &StatusReport,
+    detected: &[(String, bool)],
+    commands: &BTreeMap<String, CommandStats>,
+) -> String {
+    let mut out = String::new();
+
+    header(
+        &mut out,
+        "sentinel_overall",
+        "gauge",
+        "Overall state of the last check: 0 ok, 1 warn, 2 error.",
+    );
+    sample(
+        &mut out,
+        "sentinel_overall",
+        &[
+            ("backend", &report.backend),
+            ("profile", &report.active_profile),
+        ],
+        code(report.overall),
+    );
+
+    header(
+        &mut out,
+        "sentinel_checks",
+        "gauge",
+        "Checks in the last run by result: all of them (total), passed, warn, failed and skipped.",
+    );
+    for (result, n) in [
+        ("total", report.summary.checks_total),
+        ("passed", report.summary.checks_passed),
+        ("warn", report.summary.checks_warn),
+        ("failed", report.summary.checks_failed),
+        ("skipped", report.summary.checks_skipped),
+    ] {
+        sample(&mut out, "sentinel_checks", &[("result", result)], n);
+    }
+
+    header(
+        &mut out,
+        "sentinel_finding",
+        "gauge",
+        "Result of each evaluated check in the last run: 0 passed, 1 warn, 2 error.",
+    );
+    for f in report
+        .findings
+        .iter()
+        .filter(|f| f.status != CheckStatus::Skipped)
+    {
+        let backend = f.backend.as_deref().unwrap_or(&report.backend);
+        sample(
+            &mut out,
+            "sentinel_finding",
+            &[("id", &f.id), ("backend", backend)],
+            code(f.severity),
+        );
+    }
+
+    header(
+        &mut out,
+        "sentinel_finding_since_timestamp_seconds",
+        "gauge",
+        "Start of the current uninterrupted run of checks reporting the finding.",
+    );
+    for f in &report.findings {
+        let Some(since) = f.since else {
+            continue;
+        };
+        let backend = f.backend.as_deref().unwrap_or(&report.backend);
+        sample(
+            &mut out,
+            "sentinel_finding_since_timestamp_seconds",
+            &[("id", &f.id), ("backend", backend)],
+            since.timestamp(),
+        );
+    }
+
+    header(
+        &mut out,
+        "sentinel_last_check_timestamp_seconds",
+        "gauge",
+        "Time of the last check.",
+    );
+    sample(
+        &mut out,
+        "sentinel_last_check_timestamp_seconds",
+        &[],
+        report.last_check.timestamp_millis() as f64 / 1000.0,
+    );
+
+    header(
+        &mut out,
+        "sentinel_backend_detected",
+        "gauge",
+        "Whether the backend was detected on this host.",
+    );
+    for (backend, ok) in detected {
+        sample(
+            &mut out,
+            "sentinel_backend_detected",
+            &[("backend", backend)],
+            u8::from(*ok),
+        );
+    }
+
+    header(
+        &mut out,
+        "sentinel_command_duration_seconds",
+        "summary",
+        "Time spent running backend commands, by program.",
+    );
+    for (command, stats) in commands {
+        let labels = [("command", command.as_str())];
+        sample(
+            &mut out,
+            "sentinel_command_duration_seconds_sum",
+            &labels,
+            stats.seconds,
+        );
+        sample(
+            &mut out,
+            "sentinel_command_duration_seconds_count",
+            &labels,
+            stats.calls,
+        );
+    }
+
+    header(
+        &mut out,
+        "sentinel_command_errors_total",
+        "counter",
+        "Backend commands that could not be started or timed out, by program.",
+    );
+    for (command, stats) in commands {
+        sample(
+            &mut out,
+            "sentinel_command_errors_total",
+            &[("command", command)],
+            stats.errors,
+        );
+    }
+
+    out
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: impl std::fmt::Display) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(k, v)| format!("{k}=\"{}\"", escape(v)))
+            .collect();
+        let _ = write!(out, "{{{}}}", labels.join(","));
+    }
+    let _ = writeln!(out, " {value}");
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn code(o: Overall) -> u8 {
+    match o {
+        Overall::Ok => 0,
+        Overall::Warn => 1,
+        Overall::Error => 2,
+    }
+}
+
+/// Answer one HTTP/1.x request: `GET /metrics` gets `body`, anything else
+/// a 404 or 405. The connection is closed afterwards.
+pub async fn respond(mut stream: TcpStream, body: &str) -> Result<()> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    let read = async {
+        while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < 8192 {
+            let n = stream.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            head.extend_from_slice(&buf[..n]);
+        }
+        Ok::<_, std::io::Error>(())
+    };
+    time::timeout(Duration::from_secs(5), read)
+        .await
+        .context("timed out reading request")??;
+
+    let head = String::from_utf8_lossy(&head);
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let (method, path) = (request_line.next(), request_line.next());
+    let path = path.map(|p| p.split_once('?').map_or(p, |(p, _)| p));
+
+    let (status, content_type, body) = match (method, path) {
+        (Some("GET" | "HEAD"), Some("/metrics")) => ("200 OK", CONTENT_TYPE, body),
+        (Some("GET" | "HEAD"), _) => ("404 Not Found", "text/plain", "not found\n"),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "method not allowed\n",
+        ),
+    };
+    let mut response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    if method != Some("HEAD") {
+        response.push_str(body);
+    }
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::{Finding, Summary};
+    use chrono::{TimeZone, Utc};
+    use tokio::net::TcpListener;
+
+    fn report() -> StatusReport {
+        let mut forbidden = Finding::new("forbidden-port:23/tcp", Overall::Error, "23/tcp is open");
+        forbidden.backend = Some("nftables".into());
+        forbidden.since = Some(Utc.with_ymd_and_hms(2026, 10, 1, 12, 0, 0).unwrap());
+        StatusReport {
+            schema: crate::status::REPORT_SCHEMA,
+            overall: Overall::Error,
+            backend: "ufw+nftables".into(),
+            active_profile: "office".into(),
+            last_check: Utc.with_ymd_and_hms(2026, 10, 1, 12, 30, 0).unwrap()
+                + chrono::Duration::milliseconds(250),
+            summary: Summary {
+                checks_total: 3,
+                checks_passed: 1,
+                checks_warn: 0,
+                checks_failed: 1,
+                checks_skipped: 1,
+            },
+            findings: vec![
+                Finding::pass("missing-port:22/tcp", "22/tcp is open"),
+                forbidden,
+                Finding::skipped("missing-service:\"ssh\"", "backend inactive"),
+            ],
+        }
+    }
+
+    #[test]
+    fn renders_prometheus_text() {
+        let commands = BTreeMap::from([(
+            "nft".to_string(),
+            CommandStats {
+                calls: 2,
+                errors: 1,
+                seconds: 0.5,
+            },
+        )]);
+        let detected = [("ufw".to_string(), true), ("firewalld".to_string(), false)];
+        let text = render(&report(), &detected, &commands);
+        let samples: Vec<_> = text.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(
+            samples,
+            [
+                r#"sentinel_overall{backend="ufw+nftables",profile="office"} 2"#,
+                r#"sentinel_checks{result="total"} 3"#,
+                r#"sentinel_checks{result="passed"} 1"#,
+                r#"sentinel_checks{result="warn"} 0"#,
+                r#"sentinel_checks{result="failed"} 1"#,
+                r#"sentinel_checks{result="skipped"} 1"#,
+                r#"sentinel_finding{id="missing-port:22/tcp",backend="ufw+nftables"} 0"#,
+                r#"sentinel_finding{id="forbidden-port:23/tcp",backend="nftables"} 2"#,
+                r#"sentinel_finding_since_timestamp_seconds{id="forbidden-port:23/tcp",backend="nftables"} 1790856000"#,
+                "sentinel_last_check_timestamp_seconds 1790857800.25",
+                r#"sentinel_backend_detected{backend="ufw"} 1"#,
+                r#"sentinel_backend_detected{backend="firewalld"} 0"#,
+                r#"sentinel_command_duration_seconds_sum{command="nft"} 0.5"#,
+                r#"sentinel_command_duration_seconds_count{command="nft"} 2"#,
+                r#"sentinel_command_errors_total{command="nft"} 1"#,
+            ]
+        );
+        assert!(text.contains("# TYPE sentinel_command_duration_seconds summary\n"));
+        assert_eq!(escape("a\\b\"c\nd"), r#"a\\b\"c\nd"#);
+    }
+
+    async fn request(raw: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            respond(stream, "sentinel_overall 0\n").await.unwrap();
+        });
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client.write_all(raw.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        server.await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn serves_metrics_over_http() {
+        assert_eq!(
+            request("GET /metrics?x=1 HTTP/1.1\r\nHost: localhost\r\n\r\n").await,
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {CONTENT_TYPE}\r\nContent-Length: 19\r\nConnection: close\r\n\r\nsentinel_overall 0\n"
+            )
+        );
+        assert!(request("HEAD /metrics HTTP/1.1\r\n\r\n")
+            .await
+            .ends_with("Content-Length: 19\r\nConnection: close\r\n\r\n"));
+        assert!(request("GET / HTTP/1.1\r\n\r\n")
+            .await
+            .starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(request("POST /metrics HTTP/1.1\r\n\r\n")
+            .await
+            .starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+}
@@ -0,0 +1,92 @@
+use tracing::debug;
+
+use crate::cmd::run_timeout;
+
+/// What the host is currently connected to, as used by profile match rules.
+#[derive(Clone, Debug, Default)]
+pub struct NetworkContext {
+    /// firewalld zones with an interface or source bound to them.
+    pub zones: Vec<String>,
+    /// SSIDs of connected Wi-Fi networks.
+    pub ssids: Vec<String>,
+    /// Active NetworkManager connection names.
+    pub connections: Vec<String>,
+}
+
+/// Query firewalld and NetworkManager; whatever is unavailable stays empty.
+pub async fn detect() -> NetworkContext {
+    let mut ctx = NetworkContext::default();
+
+    match run_timeout("firewall-cmd", &["--get-active-zones"], 1500).await {
+        Ok((0, out, _err)) => {
+            ctx.zones = out
+                .lines()
+                .filter(|l| !l.starts_with(char::is_whitespace))
+                .filter_map(|l| l.split_whitespace().next())
+                .map(String::from)
+                .collect();
+        }
+        Ok(_) => {}
+        Err(err) => debug!(error = ?err, "failed to read firewalld active zones"),
+    }
+
+    let active = match run_timeout(
+        "nmcli",
+        &["-t", "-f", "NAME,TYPE", "connection", "show", "--active"],
+        2000,
+    )
+    .await
+    {
+        Ok((0, out, _err)) => out,
+        Ok(_) => String::new(),
+        Err(err) => {
+            debug!(error = ?err, "failed to list active NetworkManager connections");
+            String::new()
+        }
+    };
+
+    for line in active.lines() {
+        let fields = split_terse(line);
+        let [name, kind, ..] = fields.as_slice() else {
+            continue;
+        };
+        if kind == "802-11-wireless" {
+            let args = [
+                "-t",
+                "-g",
+                "802-11-wireless.ssid",
+                "connection",
+                "show",
+                name,
+            ];
+            match run_timeout("nmcli", &args, 2000).await {
+                Ok((0, out, _err)) if !out.trim().is_empty() => {
+                    ctx.ssids.push(out.trim().to_string())
+                }
+                Ok(_) => {}
+                Err(err) => debug!(error = ?err, connection = %name, "failed to read SSID"),
+            }
+        }
+        ctx.connections.push(name.clone());
+    }
+
+    ctx
+}
+
+/// Split an `nmcli -t` line on `:`, honouring `\:` and `\\` escapes.
+fn split_terse(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    fields.last_mut().unwrap().push(next);
+                }
+            }
+            ':' => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
@@ -0,0 +1,650 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use toml_edit::{ImDocument, Item, Key, TableLike, Value};
+
+use crate::config::{default_policy_path, parse_port_spec, Policy};
+
+/// Protocols firewalld, nftables and iptables accept in `<port>/<proto>`.
+pub const PROTOCOLS: &[&str] = &["tcp", "udp", "sctp", "dccp"];
+
+/// A policy problem, located by file and (when known) 1-based line/column.
+#[derive(Debug)]
+pub struct Problem {
+    pub file: PathBuf,
+    pub location: Option<(usize, usize)>,
+    pub msg: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.location {
+            Some((line, col)) => write!(f, "{}:{line}:{col}: {}", self.file.display(), self.msg),
+            None => write!(f, "{}: {}", self.file.display(), self.msg),
+        }
+    }
+}
+
+/// Expected shape of a policy value. Mirrors the structs in `config`.
+enum Kind {
+    Bool,
+    Int,
+    Str,
+    Strings,
+    /// Rule lists: strings in schema 1 (`<port>/<proto>` for ports), tables
+    /// in schema 2.
+    Services,
+    Ports,
+    Fragments,
+    /// Integer port number.
+    Port,
+    OneOf(&'static [&'static str]),
+    Table(&'static [Field]),
+    Tables(&'static [Field]),
+    /// Table with arbitrary keys.
+    Map(&'static Kind),
+}
+
+struct Field {
+    key: &'static str,
+    kind: Kind,
+    required: bool,
+}
+
+const fn opt(key: &'static str, kind: Kind) -> Field {
+    Field {
+        key,
+        kind,
+        required: false,
+    }
+}
+
+const fn req(key: &'static str, kind: Kind) -> Field {
+    Field {
+        key,
+        kind,
+        required: true,
+    }
+}
+
+const FAMILY: Kind = Kind::OneOf(&["ipv4", "ipv6"]);
+const SEVERITY: Kind = Kind::OneOf(&["ok", "warn", "error"]);
+
+const SERVICE_RULE: &[Field] = &[req("service", Kind::Str), opt("severity", SEVERITY)];
+const PORT_RULE: &[Field] = &[
+    req("port", Kind::Port),
+    req("protocol", Kind::OneOf(PROTOCOLS)),
+    opt("severity", SEVERITY),
+];
+const FRAGMENT_RULE: &[Field] = &[req("fragment", Kind::Str), opt("severity", SEVERITY)];
+
+const POLICY: &[Field] = &[
+    opt("include", Kind::Strings),
+    opt("schema", Kind::Int),
+    opt("backend", Kind::Str),
+    opt("backends", Kind::Strings),
+    opt("checks", Kind::Table(CHECKS)),
+    opt("profiles", Kind::Map(&Kind::Table(PROFILE))),
+];
+
+const PROFILE: &[Field] = &[
+    opt("extends", Kind::Str),
+    opt("priority", Kind::Int),
+    opt(
+        "match",
+        Kind::Table(&[
+            opt("zones", Kind::Strings),
+            opt("ssids", Kind::Strings),
+            opt("connections", Kind::Strings),
+        ]),
+    ),
+    opt("checks", Kind::Table(CHECKS)),
+];
+
+const CHECKS: &[Field] = &[
+    opt("require_firewall_active", Kind::Bool),
+    opt("required_services", Kind::Services),
+    opt("required_ports", Kind::Ports),
+    opt("required_fragments", Kind::Fragments),
+    opt("forbidden_services", Kind::Services),
+    opt("forbidden_ports", Kind::Ports),
+    opt("forbidden_fragments", Kind::Fragments),
+    opt(
+        "zones",
+        Kind::Tables(&[
+            opt("zone", Kind::Str),
+            opt("interface", Kind::Str),
+            opt("required_services", Kind::Services),
+            opt("required_ports", Kind::Ports),
+            opt("forbidden_services", Kind::Services),
+            opt("forbidden_ports", Kind::Ports),
+        ]),
+    ),
+    opt("verify_baseline", Kind::Bool),
+    opt(
+        "nftables",
+        Kind::Table(&[
+            opt("tables", Kind::Strings),
+            opt(
+                "chains",
+                Kind::Tables(&[
+                    req("table", Kind::Str),
+                    opt("chain", Kind::Str),
+                    opt("hook", Kind::Str),
+                    opt("priority", Kind::Int),
+                    opt("policy", Kind::Str),
+                ]),
+            ),
+            opt("rules", Kind::Tables(NFT_RULE)),
+            opt("forbidden_rules", Kind::Tables(NFT_RULE)),
+        ]),
+    ),
+    opt(
+        "iptables",
+        Kind::Table(&[
+            opt(
+                "chains",
+                Kind::Tables(&[
+                    opt("table", Kind::Str),
+                    req("chain", Kind::Str),
+                    opt("policy", Kind::Str),
+                    opt("family", FAMILY),
+                ]),
+            ),
+            opt("rules", Kind::Tables(IPT_RULE)),
+            opt("forbidden_rules", Kind::Tables(IPT_RULE)),
+        ]),
+    ),
+    opt(
+        "ufw",
+        Kind::Table(&[
+            opt("default_incoming", Kind::Str),
+            opt("default_outgoing", Kind::Str),
+            opt("default_routed", Kind::Str),
+        ]),
+    ),
+    opt("severity", Kind::Map(&SEVERITY)),
+];
+
+const NFT_RULE: &[Field] = &[
+    req("table", Kind::Str),
+    req("chain", Kind::Str),
+    req("protocol", Kind::Str),
+    req("dport", Kind::Port),
+    opt("verdict", Kind::Str),
+    opt("before_drop", Kind::Bool),
+];
+
+const IPT_RULE: &[Field] = &[
+    opt("table", Kind::Str),
+    req("chain", Kind::Str),
+    req("protocol", Kind::Str),
+    req("dport", Kind::Port),
+    opt("target", Kind::Str),
+    opt("before_drop", Kind::Bool),
+    opt("family", FAMILY),
+];
+
+/// Check the policy at `path`, its includes and its `policy.d` drop-ins:
+/// syntax, unknown keys, value types, port specs, and finally whether the
+/// merged policy and each of its profiles load.
+pub fn lint(path: &Path) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let is_default = default_policy_path().is_ok_and(|d| d == path);
+    if path.exists() || !is_default {
+        lint_file(path, 1, &mut Vec::new(), &mut problems);
+    }
+    // drop-ins follow the main file's schema
+    let schema = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|s| s.parse::<toml::Table>().ok())
+        .and_then(|t| t.get("schema")?.as_integer())
+        .unwrap_or(1);
+    if let Some(dir) = path.parent().map(|d| d.join(super::DROP_IN_DIR)) {
+        if dir.is_dir() {
+            match super::toml_files(&dir) {
+                Ok(files) => {
+                    for file in files {
+                        lint_file(&file, schema, &mut Vec::new(), &mut problems);
+                    }
+                }
+                Err(err) => problems.push(Problem {
+                    file: dir,
+                    location: None,
+                    msg: format!("{err:#}"),
+                }),
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        let policy = super::resolve(path).and_then(|r| r.policy());
+        let mut fail = |err: anyhow::Error| {
+            problems.push(Problem {
+                file: path.to_path_buf(),
+                location: None,
+                msg: format!("{err:#}"),
+            })
+        };
+        match policy {
+            Ok(policy) => {
+                for name in policy.profiles.iter().flat_map(|p| p.keys()) {
+                    if let Err(err) = policy.with_profile(name) {
+                        fail(err);
+                    }
+                }
+                for overlap in profile_overlaps(&policy) {
+                    fail(anyhow::anyhow!(overlap));
+                }
+            }
+            Err(err) => fail(err),
+        }
+    }
+    problems
+}
+
+/// `schema` applies unless the file sets its own; includes inherit it. A
+/// file inheriting schema 2 may write rules in either form.
+fn lint_file(path: &Path, schema: i64, stack: &mut Vec<PathBuf>, problems: &mut Vec<Problem>) {
+    let mut linter = Linter {
+        file: path.to_path_buf(),
+        raw: String::new(),
+        schema,
+        either_form: false,
+        problems,
+    };
+    let raw = match std::fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(err) => {
+            linter.report(None, format!("failed to read policy file: {err}"));
+            return;
+        }
+    };
+    linter.raw = raw.clone();
+    let doc = match ImDocument::parse(raw) {
+        Ok(doc) => doc,
+        Err(err) => {
+            let msg = err.message().trim_end().replace('\n', "; ");
+            linter.report(err.span(), msg);
+            return;
+        }
+    };
+    let root = doc.as_table();
+    match root.get("schema") {
+        Some(schema) => linter.schema = schema.as_integer().unwrap_or(linter.schema),
+        None => linter.either_form = linter.schema >= 2,
+    }
+
+    linter.check_table(root, POLICY, "");
+
+    let Some(includes) = root.get("include").and_then(Item::as_array) else {
+        return;
+    };
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    stack.push(canonical);
+    let dir = path.parent().unwrap_or(Path::new("."));
+    for inc in includes.iter() {
+        let Some(name) = inc.as_str() else {
+            continue;
+        };
+        let target = dir.join(name);
+        match target.canonicalize() {
+            Err(_) => linter.report(inc.span(), format!("included file not found: {name}")),
+            Ok(c) if stack.contains(&c) => {
+                linter.report(inc.span(), format!("include cycle: {name}"))
+            }
+            Ok(_) => lint_file(&target, linter.schema, stack, linter.problems),
+        }
+    }
+    stack.pop();
+}
+
+/// Profiles with the same priority whose match rules name the same zone,
+/// SSID or connection: when it is active, the one first by name applies.
+fn profile_overlaps(policy: &Policy) -> Vec<String> {
+    let mut overlaps = Vec::new();
+    let order = policy.selection_order();
+    for (i, (a, pa)) in order.iter().enumerate() {
+        for (b, pb) in &order[i + 1..] {
+            if pa.priority.unwrap_or(0) != pb.priority.unwrap_or(0) {
+                continue;
+            }
+            let (Some(ma), Some(mb)) = (&pa.match_rules, &pb.match_rules) else {
+                continue;
+            };
+            for (what, wa, wb) in [
+                ("zone", &ma.zones, &mb.zones),
+                ("SSID", &ma.ssids, &mb.ssids),
+                ("connection", &ma.connections, &mb.connections),
+            ] {
+                for value in wa
+                    .iter()
+                    .flatten()
+                    .filter(|v| wb.iter().flatten().any(|w| w == *v))
+                {
+                    overlaps.push(format!(
+                        "profiles {a} and {b} both match {what} {value:?} at priority {}; set `priority` on one of them (otherwise {a} applies)",
+                        pa.priority.unwrap_or(0)
+                    ));
+                }
+            }
+        }
+    }
+    overlaps
+}
+
+fn invalid_port_spec(path: &str, spec: &str) -> String {
+    format!(
+        "`{path}`: invalid port spec {spec:?} (expected <port>/<{}>)",
+        PROTOCOLS.join("|")
+    )
+}
+
+struct Linter<'a> {
+    file: PathBuf,
+    raw: String,
+    schema: i64,
+    /// Rule lists may mix schema 1 strings and schema 2 tables.
+    either_form: bool,
+    problems: &'a mut Vec<Problem>,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, span: Option<std::ops::Range<usize>>, msg: String) {
+        let location = span.map(|s| line_col(&self.raw, s.start));
+        self.problems.push(Problem {
+            file: self.file.clone(),
+            location,
+            msg,
+        });
+    }
+
+    fn check_table(&mut self, table: &dyn TableLike, fields: &[Field], path: &str) {
+        for (key, item) in table.iter() {
+            let (k, _) = table.get_key_value(key).expect("key from iter");
+            let key_path = join(path, key);
+            match fields.iter().find(|f| f.key == key) {
+                Some(field) => self.check(item, &field.kind, &key_path, k),
+                None => {
+                    let hint = suggest(key, fields)
+                        .map(|s| format!(" (did you mean `{s}`?)"))
+                        .unwrap_or_default();
+                    self.report(k.span(), format!("unknown key `{key_path}`{hint}"));
+                }
+            }
+        }
+        for field in fields.iter().filter(|f| f.required) {
+            if !table.contains_key(field.key) {
+                let span = table_span(table);
+                self.report(
+                    span,
+                    format!("missing required key `{}`", join(path, field.key)),
+                );
+            }
+        }
+    }
+
+    fn check(&mut self, item: &Item, kind: &Kind, path: &str, key: &Key) {
+        let span = item.span().or_else(|| key.span());
+        match kind {
+            Kind::Bool => self.expect(item.as_bool().is_some(), item, "a boolean", path, span),
+            Kind::Int => self.expect(item.as_integer().is_some(), item, "an integer", path, span),
+            Kind::Str => self.expect(item.as_str().is_some(), item, "a string", path, span),
+            Kind::Port => {
+                let ok = item.as_integer().is_some_and(|p| u16::try_from(p).is_ok());
+                self.expect(ok, item, "a port number (0-65535)", path, span)
+            }
+            Kind::OneOf(values) => {
+                let ok = item.as_str().is_some_and(|s| values.contains(&s));
+                let expected = format!("one of {}", values.join(", "));
+                self.expect(ok, item, &expected, path, span)
+            }
+            Kind::Services | Kind::Ports | Kind::Fragments if self.either_form => {
+                let fields = match kind {
+                    Kind::Services => SERVICE_RULE,
+                    Kind::Ports => PORT_RULE,
+                    _ => FRAGMENT_RULE,
+                };
+                let Some(array) = item.as_array() else {
+                    return self.check(item, &Kind::Tables(fields), path, key);
+                };
+                for (i, v) in array.iter().enumerate() {
+                    let elem_path = format!("{path}[{i}]");
+                    match (v.as_inline_table(), v.as_str()) {
+                        (Some(t), _) => self.check_table(t, fields, &elem_path),
+                        (None, Some(s)) => {
+                            if matches!(kind, Kind::Ports) && !is_valid_port_spec(s) {
+                                self.report(v.span(), invalid_port_spec(&elem_path, s));
+                            }
+                        }
+                        (None, None) => self.report(
+                            v.span(),
+                            format!(
+                                "`{elem_path}` must be a string or a table, found {}",
+                                value_type(v)
+                            ),
+                        ),
+                    }
+                }
+            }
+            Kind::Services if self.schema >= 2 => {
+                self.check(item, &Kind::Tables(SERVICE_RULE), path, key)
+            }
+            Kind::Ports if self.schema >= 2 => {
+                self.check(item, &Kind::Tables(PORT_RULE), path, key)
+            }
+            Kind::Fragments if self.schema >= 2 => {
+                self.check(item, &Kind::Tables(FRAGMENT_RULE), path, key)
+            }
+            Kind::Strings | Kind::Services | Kind::Ports | Kind::Fragments => {
+                let Some(array) = item.as_array() else {
+                    return self.expect(false, item, "a list of strings", path, span);
+                };
+                for (i, v) in array.iter().enumerate() {
+                    let elem_path = format!("{path}[{i}]");
+                    let Some(s) = v.as_str() else {
+                        let found = value_type(v);
+                        self.report(
+                            v.span(),
+                            format!("`{elem_path}` must be a string, found {found}"),
+                        );
+                        continue;
+                    };
+                    if matches!(kind, Kind::Ports) && !is_valid_port_spec(s) {
+                        self.report(v.span(), invalid_port_spec(&elem_path, s));
+                    }
+                }
+            }
+            Kind::Table(fields) => match item.as_table_like() {
+                Some(t) => self.check_table(t, fields, path),
+                None => self.expect(false, item, "a table", path, span),
+            },
+            Kind::Map(value_kind) => match item.as_table_like() {
+                Some(t) => {
+                    for (k, v) in t.iter() {
+                        let (key, _) = t.get_key_value(k).expect("key from iter");
+                        self.check(v, value_kind, &join(path, k), key);
+                    }
+                }
+                None => self.expect(false, item, "a table", path, span),
+            },
+            Kind::Tables(fields) => {
+                if let Some(tables) = item.as_array_of_tables() {
+                    for (i, t) in tables.iter().enumerate() {
+                        self.check_table(t, fields, &format!("{path}[{i}]"));
+                    }
+                } else if let Some(array) = item.as_array() {
+                    for (i, v) in array.iter().enumerate() {
+                        let elem_path = format!("{path}[{i}]");
+                        match v.as_inline_table() {
+                            Some(t) => self.check_table(t, fields, &elem_path),
+                            None => self.report(
+                                v.span(),
+                                format!("`{elem_path}` must be a table, found {}", value_type(v)),
+                            ),
+                        }
+                    }
+                } else {
+                    self.expect(false, item, "a list of tables", path, span)
+                }
+            }
+        }
+    }
+
+    fn expect(
+        &mut self,
+        ok: bool,
+        item: &Item,
+        expected: &str,
+        path: &str,
+        span: Option<std::ops::Range<usize>>,
+    ) {
+        if !ok {
+            let found = match item {
+                Item::Value(Value::String(s)) => format!("{:?}", s.value()),
+                Item::Value(Value::Integer(i)) => i.value().to_string(),
+                _ => item_type(item).to_string(),
+            };
+            self.report(span, format!("`{path}` must be {expected}, found {found}"));
+        }
+    }
+}
+
+fn table_span(table: &dyn TableLike) -> Option<std::ops::Range<usize>> {
+    table
+        .iter()
+        .find_map(|(k, _)| table.get_key_value(k).and_then(|(key, _)| key.span()))
+}
+
+fn is_valid_port_spec(s: &str) -> bool {
+    parse_port_spec(s).is_some_and(|(_, proto)| PROTOCOLS.contains(&proto))
+}
+
+/// Closest known key within a small edit distance (`required_port` ->
+/// `required_ports`).
+fn suggest(key: &str, fields: &[Field]) -> Option<&'static str> {
+    fields
+        .iter()
+        .map(|f| (edit_distance(key, f.key), f.key))
+        .filter(|(d, k)| *d <= 2.max(k.len() / 4))
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, k)| k)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            cur.push((prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1));
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+fn item_type(item: &Item) -> &'static str {
+    match item {
+        Item::None => "nothing",
+        Item::Value(v) => value_type(v),
+        Item::Table(_) => "a table",
+        Item::ArrayOfTables(_) => "a list of tables",
+    }
+}
+
+fn value_type(v: &Value) -> &'static str {
+    match v {
+        Value::String(_) => "a string",
+        Value::Integer(_) => "an integer",
+        Value::Float(_) => "a float",
+        Value::Boolean(_) => "a boolean",
+        Value::Datetime(_) => "a datetime",
+        Value::Array(_) => "a list",
+        Value::InlineTable(_) => "a table",
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// 1-based line and column (in characters) of byte `offset`.
+fn line_col(raw: &str, offset: usize) -> (usize, usize) {
+    let before = &raw[..offset.min(raw.len())];
+    let line = before.matches('\n').count() + 1;
+    let col = before
+        .rsplit_once('\n')
+        .map_or(before, |(_, l)| l)
+        .chars()
+        .count()
+        + 1;
+    (line, col)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(name: &str, files: &[(&str, &str)]) -> Vec<String> {
+        let dir = std::env::temp_dir().join(format!("sentinel-lint-{name}-{}", std::process::id()));
+        for (file, contents) in files {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        let found = lint(&dir.join("policy.toml"))
+            .iter()
+            .map(|p| {
+                let file = p.file.strip_prefix(&dir).unwrap().display();
+                match p.location {
+                    Some((line, col)) => format!("{file}:{line}:{col}: {}", p.msg),
+                    None => format!("{file}: {}", p.msg),
+                }
+            })
+            .collect();
+        let _ = std::fs::remove_dir_all(dir);
+        found
+    }
+
+    #[test]
+    fn reports_problems_with_their_location() {
+        let policy = r#"schema = 1
+
+[checks]
+required_port = ["22/tcp"]
+forbidden_ports = ["ssh", 23]
+verify_baseline = "yes"
+
+[checks.nftables]
+chians = []
+"#;
+        assert_eq!(
+            problems(
+                "located",
+                &[
+                    ("policy.toml", policy),
+                    ("policy.d/10-bad.toml", "[checks\n"),
+                ]
+            ),
+            [
+                "policy.toml:4:1: unknown key `checks.required_port` (did you mean `required_ports`?)",
+                "policy.toml:5:20: `checks.forbidden_ports[0]`: invalid port spec \"ssh\" (expected <port>/<tcp|udp|sctp|dccp>)",
+                "policy.toml:5:27: `checks.forbidden_ports[1]` must be a string, found an integer",
+                "policy.toml:6:19: `checks.verify_baseline` must be a boolean, found \"yes\"",
+                "policy.toml:9:1: unknown key `checks.nftables.chians` (did you mean `chains`?)",
+                "policy.d/10-bad.toml:1:8: invalid table header; expected `.`, `]`",
+            ]
+        );
+    }
+
+    #[test]
+    fn clean_policy_has_no_problems() {
+        let policy = "schema = 1\n[checks]\nrequired_ports = [\"22/tcp\"]\n";
+        assert!(problems("clean", &[("policy.toml", policy)]).is_empty());
+    }
+}
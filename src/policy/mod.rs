@@ -0,0 +1,461 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::config::{default_policy_path, Policy};
+
+pub mod lint;
+pub mod v2;
+
+/// Drop-in directory next to the main policy file.
+pub const DROP_IN_DIR: &str = "policy.d";
+
+/// Source label for values from the built-in default policy.
+const BUILTIN: &str = "<built-in default>";
+
+/// Policy tables merged from the main file, its `include`s and the
+/// `policy.d/*.toml` drop-ins, with the file every value came from. Each file
+/// is converted to the schema 1 layout the [`Policy`] structs use before it
+/// is merged, so files in different schemas can be mixed.
+#[derive(Debug, Default)]
+pub struct Resolved {
+    pub table: toml::Table,
+    /// Source of every scalar, table-array element and list item, keyed by
+    /// dotted path (`checks.required_ports[1]`).
+    pub sources: BTreeMap<String, PathBuf>,
+    /// Files in merge order.
+    pub files: Vec<PathBuf>,
+}
+
+impl Resolved {
+    /// The merged policy.
+    pub fn policy(&self) -> Result<Policy> {
+        toml::Value::Table(self.table.clone())
+            .try_into()
+            .context("failed to parse policy TOML")
+    }
+}
+
+/// Merge order: each file's `include`s (in listed order, paths relative to
+/// the including file) before the file itself, then `policy.d/*.toml` next
+/// to the main file in file name order. Later scalars replace earlier ones,
+/// tables merge key by key and lists are concatenated without duplicates.
+///
+/// A file without a `schema` key is read in the schema of the file including
+/// it (drop-ins: the main file's), and may then also write rules in the
+/// schema 1 string form.
+///
+/// A missing default policy file falls back to the built-in default policy,
+/// with drop-ins still applied on top.
+pub fn resolve(path: &Path) -> Result<Resolved> {
+    let mut resolved = Resolved::default();
+    let mut schema = 1;
+    if path.exists() {
+        schema = merge_file(path, 1, &mut resolved, &mut Vec::new())?;
+    } else if path == default_policy_path()? {
+        tracing::warn!(path = %path.display(), "policy file not found; using default inline policy");
+        let builtin = Path::new(BUILTIN);
+        if let toml::Value::Table(t) = toml::Value::try_from(Policy::default())? {
+            merge_table(&mut resolved.table, t, builtin, "", &mut resolved.sources);
+        }
+        resolved.files.push(builtin.to_path_buf());
+    } else {
+        anyhow::bail!("policy file not found: {}", path.display());
+    }
+
+    if let Some(dir) = path.parent().map(|d| d.join(DROP_IN_DIR)) {
+        if dir.is_dir() {
+            for file in toml_files(&dir)? {
+                merge_file(&file, schema, &mut resolved, &mut Vec::new())?;
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+/// Merge `path` (after its includes); returns the file's schema.
+fn merge_file(
+    path: &Path,
+    schema: i64,
+    resolved: &mut Resolved,
+    stack: &mut Vec<PathBuf>,
+) -> Result<i64> {
+    let canonical = path
+        .canonicalize()
+        .with_context(|| format!("policy file not found: {}", path.display()))?;
+    anyhow::ensure!(
+        !stack.contains(&canonical),
+        "policy include cycle at: {}",
+        path.display()
+    );
+
+    let s = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read policy file: {}", path.display()))?;
+    let mut table: toml::Table = toml::from_str(&s)
+        .with_context(|| format!("failed to parse policy TOML: {}", path.display()))?;
+    let declared = table.contains_key("schema");
+    let schema = match table.get("schema") {
+        None => schema,
+        Some(v) => v
+            .as_integer()
+            .with_context(|| format!("`schema` must be an integer in {}", path.display()))?,
+    };
+
+    stack.push(canonical);
+    if let Some(includes) = table.remove("include") {
+        let includes: Vec<String> = includes
+            .try_into()
+            .with_context(|| format!("`include` must be a list of paths in {}", path.display()))?;
+        let dir = path.parent().unwrap_or(Path::new("."));
+        for inc in includes {
+            merge_file(&dir.join(inc), schema, resolved, stack)?;
+        }
+    }
+    stack.pop();
+
+    match schema {
+        1 => {}
+        n if n == i64::from(v2::LATEST) => v2::to_internal(&mut table, declared)
+            .with_context(|| format!("invalid schema {n} policy: {}", path.display()))?,
+        n => anyhow::bail!("unsupported policy schema {n}: {}", path.display()),
+    }
+    merge_table(&mut resolved.table, table, path, "", &mut resolved.sources);
+    resolved.files.push(path.to_path_buf());
+    Ok(schema)
+}
+
+fn merge_table(
+    base: &mut toml::Table,
+    overlay: toml::Table,
+    src: &Path,
+    prefix: &str,
+    sources: &mut BTreeMap<String, PathBuf>,
+) {
+    for (key, value) in overlay {
+        let path = join_key(prefix, &key);
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(b)), toml::Value::Table(o)) => {
+                merge_table(b, o, src, &path, sources)
+            }
+            (Some(toml::Value::Array(b)), toml::Value::Array(o)) => {
+                for item in o {
+                    if !b.contains(&item) {
+                        record(&format!("{path}[{}]", b.len()), &item, src, sources);
+                        b.push(item);
+                    }
+                }
+            }
+            (_, value) => {
+                sources.retain(|k, _| !is_under(k, &path));
+                record(&path, &value, src, sources);
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn record(path: &str, value: &toml::Value, src: &Path, sources: &mut BTreeMap<String, PathBuf>) {
+    match value {
+        toml::Value::Table(t) => {
+            for (k, v) in t {
+                record(&join_key(path, k), v, src, sources);
+            }
+        }
+        toml::Value::Array(items) => {
+            for (i, v) in items.iter().enumerate() {
+                record(&format!("{path}[{i}]"), v, src, sources);
+            }
+        }
+        _ => {
+            sources.insert(path.to_string(), src.to_path_buf());
+        }
+    }
+}
+
+fn is_under(key: &str, path: &str) -> bool {
+    key.strip_prefix(path)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.') || rest.starts_with('['))
+}
+
+fn join_key(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+fn toml_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("failed to read drop-in dir: {}", dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "toml") && p.is_file())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// The merged policy as TOML, each value annotated with its source file.
+pub fn render_resolved(resolved: &Resolved) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# merged from (in order):");
+    for f in &resolved.files {
+        let _ = writeln!(out, "#   {}", f.display());
+    }
+    render_table(&resolved.table, "", &resolved.sources, &mut out);
+    out
+}
+
+fn render_table(
+    table: &toml::Table,
+    path: &str,
+    sources: &BTreeMap<String, PathBuf>,
+    out: &mut String,
+) {
+    let src = |p: &str| {
+        sources
+            .get(p)
+            .map(|s| format!("  # {}", s.display()))
+            .unwrap_or_default()
+    };
+
+    for (key, value) in table {
+        let p = join_key(path, key);
+        match value {
+            toml::Value::Table(_) => {}
+            toml::Value::Array(items) if is_table_array(items) => {}
+            toml::Value::Array(items) if items.is_empty() => {
+                let _ = writeln!(out, "{} = []", bare_key(key));
+            }
+            toml::Value::Array(items) => {
+                let _ = writeln!(out, "{} = [", bare_key(key));
+                for (i, item) in items.iter().enumerate() {
+                    let _ = writeln!(out, "  {item},{}", src(&format!("{p}[{i}]")));
+                }
+                let _ = writeln!(out, "]");
+            }
+            v => {
+                let _ = writeln!(out, "{} = {v}{}", bare_key(key), src(&p));
+            }
+        }
+    }
+
+    for (key, value) in table {
+        let p = join_key(path, key);
+        match value {
+            toml::Value::Table(t) => {
+                // like `toml`, skip headers of tables that only hold tables
+                let only_tables = !t.is_empty()
+                    && t.values().all(|v| match v {
+                        toml::Value::Table(_) => true,
+                        toml::Value::Array(items) => is_table_array(items),
+                        _ => false,
+                    });
+                if !only_tables {
+                    let _ = writeln!(out, "\n[{}]", header(&p));
+                }
+                render_table(t, &p, sources, out);
+            }
+            toml::Value::Array(items) if is_table_array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    let item_path = format!("{p}[{i}]");
+                    let from = sources
+                        .iter()
+                        .find(|(k, _)| is_under(k, &item_path))
+                        .map(|(_, s)| format!("  # {}", s.display()))
+                        .unwrap_or_default();
+                    let _ = writeln!(out, "\n[[{}]]{from}", header(&p));
+                    if let toml::Value::Table(t) = item {
+                        render_table(t, &item_path, sources, out);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_table_array(items: &[toml::Value]) -> bool {
+    !items.is_empty() && items.iter().all(toml::Value::is_table)
+}
+
+/// `checks.zones[0].x` -> `checks.zones.x` with keys quoted where needed.
+fn header(path: &str) -> String {
+    let mut parts = Vec::new();
+    let mut rest = path;
+    while !rest.is_empty() {
+        let end = rest.find(['.', '[']).unwrap_or(rest.len());
+        parts.push(bare_key(&rest[..end]));
+        rest = &rest[end..];
+        if let Some(r) = rest.strip_prefix('[') {
+            rest = r.split_once(']').map_or("", |(_, r)| r);
+        }
+        rest = rest.strip_prefix('.').unwrap_or(rest);
+    }
+    parts.join(".")
+}
+
+fn bare_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        key.to_string()
+    } else {
+        toml::Value::String(key.to_string()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write `files` (relative path, contents) into a fresh directory.
+    fn policy_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("sentinel-policy-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for (file, contents) in files {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    const V2_MAIN: &str = r#"
+schema = 2
+[checks]
+required_ports = [{ port = 22, protocol = "tcp", severity = "error" }]
+"#;
+
+    #[test]
+    fn schema_2_main_with_schema_less_drop_in() {
+        let dir = policy_dir(
+            "schemaless",
+            &[
+                ("policy.toml", V2_MAIN),
+                (
+                    "policy.d/10-web.toml",
+                    "[checks]\nrequired_ports = [\"443/tcp\", { port = 80, protocol = \"tcp\" }]\n",
+                ),
+            ],
+        );
+        let path = dir.join("policy.toml");
+        let policy = resolve(&path).unwrap().policy().unwrap();
+        let checks = policy.checks.unwrap();
+        assert_eq!(
+            checks.required_ports.unwrap(),
+            ["22/tcp", "443/tcp", "80/tcp"]
+        );
+        assert_eq!(
+            checks.severity.unwrap()["missing-port:22/tcp"],
+            crate::status::Overall::Error
+        );
+        assert!(lint::lint(&path).is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn schema_1_drop_in_after_schema_2_main() {
+        let dir = policy_dir(
+            "v1dropin",
+            &[
+                ("policy.toml", V2_MAIN),
+                (
+                    "policy.d/10-legacy.toml",
+                    "schema = 1\n[checks]\nforbidden_services = [\"telnet\"]\n",
+                ),
+            ],
+        );
+        let path = dir.join("policy.toml");
+        let checks = resolve(&path).unwrap().policy().unwrap().checks.unwrap();
+        assert_eq!(checks.required_ports.unwrap(), ["22/tcp"]);
+        assert_eq!(checks.forbidden_services.unwrap(), ["telnet"]);
+        assert!(lint::lint(&path).is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn schema_2_include_from_schema_1_main() {
+        let dir = policy_dir(
+            "v2include",
+            &[
+                (
+                    "policy.toml",
+                    "schema = 1\ninclude = [\"ssh.toml\"]\n[checks]\nrequired_services = [\"ssh\"]\n",
+                ),
+                (
+                    "ssh.toml",
+                    "schema = 2\n[checks]\nforbidden_ports = [{ port = 23, protocol = \"tcp\", severity = \"warn\" }]\n",
+                ),
+            ],
+        );
+        let path = dir.join("policy.toml");
+        let checks = resolve(&path).unwrap().policy().unwrap().checks.unwrap();
+        assert_eq!(checks.required_services.unwrap(), ["ssh"]);
+        assert_eq!(checks.forbidden_ports.unwrap(), ["23/tcp"]);
+        assert!(checks
+            .severity
+            .unwrap()
+            .contains_key("forbidden-port:23/tcp"));
+        assert!(lint::lint(&path).is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn string_rules_in_a_declared_schema_2_file_are_rejected() {
+        let dir = policy_dir(
+            "strict",
+            &[(
+                "policy.toml",
+                "schema = 2\n[checks]\nrequired_ports = [\"22/tcp\"]\n",
+            )],
+        );
+        let path = dir.join("policy.toml");
+        let err = resolve(&path).unwrap_err();
+        assert!(format!("{err:#}").contains("must be a table in schema 2"));
+        assert!(!lint::lint(&path).is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    const PROFILES: &str = r#"
+schema = 1
+
+[profiles.home.match]
+connections = ["Home"]
+zones = ["home"]
+
+[profiles.anywhere.match]
+zones = ["home", "public"]
+"#;
+
+    #[test]
+    fn profiles_are_selected_by_priority_then_name() {
+        let net = crate::network::NetworkContext {
+            zones: vec!["home".into()],
+            ..Default::default()
+        };
+        let dir = policy_dir("profiles", &[("policy.toml", PROFILES)]);
+        let path = dir.join("policy.toml");
+        let policy = resolve(&path).unwrap().policy().unwrap();
+        assert_eq!(policy.matching_profile(&net), Some("anywhere"));
+        let problems: Vec<String> = lint::lint(&path).iter().map(|p| p.msg.clone()).collect();
+        assert_eq!(
+            problems,
+            ["profiles anywhere and home both match zone \"home\" at priority 0; set `priority` on one of them (otherwise anywhere applies)"]
+        );
+
+        let prioritized = PROFILES.replace(
+            "[profiles.home.match]",
+            "[profiles.home]\npriority = 5\n[profiles.home.match]",
+        );
+        std::fs::write(&path, prioritized).unwrap();
+        let policy = resolve(&path).unwrap().policy().unwrap();
+        assert_eq!(policy.matching_profile(&net), Some("home"));
+        assert!(lint::lint(&path).is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
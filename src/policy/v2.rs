@@ -0,0 +1,384 @@
+use anyhow::{bail, Context, Result};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use toml_edit::{DocumentMut, InlineTable, Item, TableLike};
+
+use crate::{config::parse_port_spec, status::Overall};
+
+/// Newest policy schema; `policy migrate` rewrites schema 1 files to it.
+///
+/// Schema 2 turns the rule lists of `[checks]` (also inside `[[checks.zones]]`
+/// and profile checks) into typed tables with an optional per-rule severity:
+/// `{ service = "ssh" }`, `{ port = 22, protocol = "tcp", severity = "error" }`,
+/// `{ fragment = "tcp dport 22 accept" }`. Everything else is unchanged.
+pub const LATEST: u32 = 2;
+
+/// `{ service = "ssh" }`
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ServiceRule {
+    /// firewalld service or ufw application profile.
+    pub service: String,
+    pub severity: Option<Overall>,
+}
+
+/// `{ port = 22, protocol = "tcp" }`
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PortRule {
+    pub port: u16,
+    /// `tcp`, `udp`, `sctp` or `dccp`.
+    pub protocol: String,
+    pub severity: Option<Overall>,
+}
+
+/// `{ fragment = "tcp dport 22 accept" }`
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FragmentRule {
+    /// Rule text to look for (`nft` tokens or an `iptables-save` line).
+    pub fragment: String,
+    pub severity: Option<Overall>,
+}
+
+#[derive(Clone, Copy)]
+enum Shape {
+    Service,
+    Port,
+    Fragment,
+}
+
+struct RuleList {
+    key: &'static str,
+    /// Finding kind reported for the list's rules.
+    kind: &'static str,
+    shape: Shape,
+}
+
+const RULE_LISTS: &[RuleList] = &[
+    RuleList {
+        key: "required_services",
+        kind: "missing-service",
+        shape: Shape::Service,
+    },
+    RuleList {
+        key: "forbidden_services",
+        kind: "forbidden-service",
+        shape: Shape::Service,
+    },
+    RuleList {
+        key: "required_ports",
+        kind: "missing-port",
+        shape: Shape::Port,
+    },
+    RuleList {
+        key: "forbidden_ports",
+        kind: "forbidden-port",
+        shape: Shape::Port,
+    },
+    RuleList {
+        key: "required_fragments",
+        kind: "missing-fragment",
+        shape: Shape::Fragment,
+    },
+    RuleList {
+        key: "forbidden_fragments",
+        kind: "forbidden-fragment",
+        shape: Shape::Fragment,
+    },
+];
+
+impl Shape {
+    fn example(self) -> &'static str {
+        match self {
+            Shape::Service => r#"{ service = "ssh" }"#,
+            Shape::Port => r#"{ port = 22, protocol = "tcp" }"#,
+            Shape::Fragment => r#"{ fragment = "tcp dport 22 accept" }"#,
+        }
+    }
+
+    /// Schema 1 string and severity for a schema 2 rule table.
+    fn to_v1(self, rule: &toml::Table) -> Result<(String, Option<Overall>)> {
+        let rule = toml::Value::Table(rule.clone());
+        Ok(match self {
+            Shape::Service => {
+                let r: ServiceRule = rule.try_into()?;
+                (r.service, r.severity)
+            }
+            Shape::Port => {
+                let r: PortRule = rule.try_into()?;
+                (format!("{}/{}", r.port, r.protocol), r.severity)
+            }
+            Shape::Fragment => {
+                let r: FragmentRule = rule.try_into()?;
+                (r.fragment, r.severity)
+            }
+        })
+    }
+
+    /// Schema 2 rule table for a schema 1 string.
+    fn upgrade(self, s: &str) -> Option<InlineTable> {
+        let mut rule = InlineTable::new();
+        match self {
+            Shape::Service => {
+                rule.insert("service", s.into());
+            }
+            Shape::Fragment => {
+                rule.insert("fragment", s.into());
+            }
+            Shape::Port => {
+                let (port, proto) = parse_port_spec(s)?;
+                rule.insert("port", i64::from(port).into());
+                rule.insert("protocol", proto.into());
+            }
+        }
+        Some(rule)
+    }
+}
+
+/// Convert a schema 2 policy table to the schema 1 layout the [`Policy`]
+/// structs use. Per-rule severities become exact-id entries in the checks'
+/// `severity` table. Unless `strict`, rules already in the schema 1 string
+/// form are accepted as well (files that inherit schema 2 from the file
+/// including them).
+///
+/// [`Policy`]: crate::config::Policy
+pub fn to_internal(table: &mut toml::Table, strict: bool) -> Result<()> {
+    if let Some(checks) = table.get_mut("checks").and_then(toml::Value::as_table_mut) {
+        checks_to_v1(checks, "checks", strict)?;
+    }
+    if let Some(profiles) = table
+        .get_mut("profiles")
+        .and_then(toml::Value::as_table_mut)
+    {
+        for (name, profile) in profiles.iter_mut() {
+            let checks = profile
+                .as_table_mut()
+                .and_then(|p| p.get_mut("checks"))
+                .and_then(toml::Value::as_table_mut);
+            if let Some(checks) = checks {
+                checks_to_v1(checks, &format!("profiles.{name}.checks"), strict)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn checks_to_v1(checks: &mut toml::Table, path: &str, strict: bool) -> Result<()> {
+    let mut severities = Vec::new();
+    for (kind, spec, severity) in lists_to_v1(checks, path, strict)? {
+        severities.push((format!("{kind}:{spec}"), severity));
+    }
+
+    if let Some(zones) = checks.get_mut("zones").and_then(toml::Value::as_array_mut) {
+        for (i, zone) in zones.iter_mut().enumerate() {
+            let Some(zone) = zone.as_table_mut() else {
+                continue;
+            };
+            let zone_path = format!("{path}.zones[{i}]");
+            let rules = lists_to_v1(zone, &zone_path, strict)?;
+            if rules.is_empty() {
+                continue;
+            }
+            // zone finding ids name the zone, which is unknown for `interface`
+            let Some(name) = zone.get("zone").and_then(toml::Value::as_str) else {
+                bail!("`{zone_path}`: per-rule `severity` needs `zone` to be set");
+            };
+            for (kind, spec, severity) in rules {
+                severities.push((format!("{kind}:{name}/{spec}"), severity));
+            }
+        }
+    }
+
+    if !severities.is_empty() {
+        let map = checks
+            .entry("severity")
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .with_context(|| format!("`{path}.severity` must be a table"))?;
+        for (id, severity) in severities {
+            map.insert(id, severity.as_str().into());
+        }
+    }
+    Ok(())
+}
+
+/// Replace rule tables with their schema 1 strings; returns the rules that
+/// carry a severity as `(finding kind, spec, severity)`.
+fn lists_to_v1(
+    table: &mut toml::Table,
+    path: &str,
+    strict: bool,
+) -> Result<Vec<(&'static str, String, Overall)>> {
+    let mut severities = Vec::new();
+    for list in RULE_LISTS {
+        let Some(items) = table.get_mut(list.key).and_then(toml::Value::as_array_mut) else {
+            continue;
+        };
+        for (i, item) in items.iter_mut().enumerate() {
+            let at = format!("{path}.{}[{i}]", list.key);
+            if !strict && item.is_str() {
+                continue;
+            }
+            let Some(rule) = item.as_table() else {
+                bail!(
+                    "`{at}` must be a table in schema 2, e.g. {} (see `sentinelctl policy migrate`)",
+                    list.shape.example()
+                );
+            };
+            let (spec, severity) = list
+                .shape
+                .to_v1(rule)
+                .with_context(|| format!("invalid rule `{at}`"))?;
+            if let Some(severity) = severity {
+                severities.push((list.kind, spec.clone(), severity));
+            }
+            *item = toml::Value::String(spec);
+        }
+    }
+    Ok(severities)
+}
+
+/// Rewrite a policy file in schema 2, keeping comments and layout:
+/// `schema = 1` becomes `schema = 2` and string rules become tables. Rules
+/// already in table form are left alone, so included files without a
+/// `schema` key and already migrated files can be run through it again.
+/// Fails, listing them, if some string rules have no schema 2 form (schema 2
+/// loading would reject the file).
+pub fn migrate(raw: &str) -> Result<String> {
+    let mut doc: DocumentMut = raw.parse().context("failed to parse policy TOML")?;
+    let mut notes = Vec::new();
+
+    if let Some(schema) = doc.get_mut("schema").and_then(Item::as_value_mut) {
+        if schema.as_integer() == Some(1) {
+            let decor = schema.decor().clone();
+            *schema = i64::from(LATEST).into();
+            *schema.decor_mut() = decor;
+        }
+    }
+    if let Some(checks) = doc.get_mut("checks").and_then(Item::as_table_like_mut) {
+        migrate_checks(checks, "checks", &mut notes);
+    }
+    if let Some(profiles) = doc.get_mut("profiles").and_then(Item::as_table_like_mut) {
+        for (name, profile) in profiles.iter_mut() {
+            let checks = profile
+                .as_table_like_mut()
+                .and_then(|p| p.get_mut("checks"))
+                .and_then(Item::as_table_like_mut);
+            if let Some(checks) = checks {
+                migrate_checks(
+                    checks,
+                    &format!("profiles.{}.checks", name.get()),
+                    &mut notes,
+                );
+            }
+        }
+    }
+    if !notes.is_empty() {
+        bail!(
+            "cannot convert {} rule(s); fix or remove them first:\n  {}",
+            notes.len(),
+            notes.join("\n  ")
+        );
+    }
+    Ok(doc.to_string())
+}
+
+fn migrate_checks(checks: &mut dyn TableLike, path: &str, notes: &mut Vec<String>) {
+    migrate_lists(checks, path, notes);
+    match checks.get_mut("zones") {
+        Some(Item::ArrayOfTables(zones)) => {
+            for (i, zone) in zones.iter_mut().enumerate() {
+                migrate_lists(zone, &format!("{path}.zones[{i}]"), notes);
+            }
+        }
+        Some(Item::Value(toml_edit::Value::Array(zones))) => {
+            for (i, zone) in zones.iter_mut().enumerate() {
+                if let Some(zone) = zone.as_inline_table_mut() {
+                    migrate_lists(zone, &format!("{path}.zones[{i}]"), notes);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn migrate_lists(table: &mut dyn TableLike, path: &str, notes: &mut Vec<String>) {
+    for list in RULE_LISTS {
+        let Some(items) = table.get_mut(list.key).and_then(Item::as_array_mut) else {
+            continue;
+        };
+        for (i, item) in items.iter_mut().enumerate() {
+            let Some(s) = item.as_str() else {
+                continue;
+            };
+            match list.shape.upgrade(s) {
+                Some(rule) => {
+                    let decor = item.decor().clone();
+                    *item = toml_edit::Value::InlineTable(rule);
+                    *item.decor_mut() = decor;
+                }
+                None => notes.push(format!(
+                    "`{path}.{}[{i}]`: {s:?} is not of the form {}",
+                    list.key,
+                    list.shape.example()
+                )),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_converts_string_rules_and_keeps_comments() {
+        let raw = r#"schema = 1 # current
+
+[checks]
+# who may log in
+required_services = ["ssh"]
+forbidden_ports = ["3389/tcp"]
+
+[[checks.zones]]
+zone = "work"
+required_fragments = ["tcp dport 22 accept"]
+"#;
+        let migrated = migrate(raw).unwrap();
+        assert_eq!(
+            migrated,
+            r#"schema = 2 # current
+
+[checks]
+# who may log in
+required_services = [{ service = "ssh" }]
+forbidden_ports = [{ port = 3389, protocol = "tcp" }]
+
+[[checks.zones]]
+zone = "work"
+required_fragments = [{ fragment = "tcp dport 22 accept" }]
+"#
+        );
+        // already migrated files are left as they are
+        assert_eq!(migrate(&migrated).unwrap(), migrated);
+    }
+
+    #[test]
+    fn migrate_refuses_rules_without_a_schema_2_form() {
+        let raw = r#"schema = 1
+[checks]
+forbidden_ports = ["23/tcp", "6000-6007/tcp"]
+
+[profiles.travel.checks]
+required_ports = ["ssh"]
+"#;
+        let err = format!("{:#}", migrate(raw).unwrap_err());
+        assert_eq!(
+            err,
+            r#"cannot convert 2 rule(s); fix or remove them first:
+  `checks.forbidden_ports[1]`: "6000-6007/tcp" is not of the form { port = 22, protocol = "tcp" }
+  `profiles.travel.checks.required_ports[0]`: "ssh" is not of the form { port = 22, protocol = "tcp" }"#
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::{bail, Result};
+use schemars::generate::SchemaSettings;
+use serde_json::{json, Value};
+
+use crate::{
+    config::Policy,
+    policy::{
+        lint::PROTOCOLS,
+        v2::{self, FragmentRule, PortRule, ServiceRule},
+    },
+    status::StatusReport,
+};
+
+/// JSON Schema of a policy file in the given schema version, generated from
+/// the `config` types (which use the schema 1 layout) and, for schema 2, with
+/// the rule lists swapped for their typed rule tables.
+pub fn policy(version: u32) -> Result<Value> {
+    if !(1..=v2::LATEST).contains(&version) {
+        bail!("unsupported policy schema: {version}");
+    }
+    let mut generator = SchemaSettings::draft2020_12().into_generator();
+    let rules = if version >= 2 {
+        vec![
+            ("services", generator.subschema_for::<ServiceRule>()),
+            ("ports", generator.subschema_for::<PortRule>()),
+            ("fragments", generator.subschema_for::<FragmentRule>()),
+        ]
+    } else {
+        Vec::new()
+    };
+    let mut schema = generator.into_root_schema_for::<Policy>().to_value();
+
+    schema["title"] = json!(format!("sentinel policy (schema {version})"));
+    let props = &mut schema["properties"];
+    props["schema"] = json!({ "const": version });
+    props["include"] = json!({
+        "description": "Policy files merged in before this one, relative to it.",
+        "type": "array",
+        "items": { "type": "string" },
+    });
+
+    let protocols = PROTOCOLS.join("|");
+    for def in ["Checks", "ZoneChecks"] {
+        let props = schema.pointer_mut(&format!("/$defs/{def}/properties"));
+        let Some(props) = props.and_then(Value::as_object_mut) else {
+            continue;
+        };
+        for prefix in ["required", "forbidden"] {
+            if let Some(list) = props.get_mut(&format!("{prefix}_ports")) {
+                list["items"]["pattern"] = json!(format!("^[0-9]+/({protocols})$"));
+            }
+            for (suffix, rule) in &rules {
+                if let Some(list) = props.get_mut(&format!("{prefix}_{suffix}")) {
+                    list["items"] = rule.as_value().clone();
+                }
+            }
+        }
+    }
+    if let Some(protocol) = schema.pointer_mut("/$defs/PortRule/properties/protocol") {
+        protocol["enum"] = json!(PROTOCOLS);
+    }
+    Ok(schema)
+}
+
+/// JSON Schema of the status report (`status --json`, `--output`).
+pub fn status() -> Value {
+    SchemaSettings::draft2020_12()
+        .into_generator()
+        .into_root_schema_for::<StatusReport>()
+        .to_value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::{Evidence, Finding, Overall, Summary, REPORT_SCHEMA};
+
+    fn errors(schema: &Value, instance: &Value) -> Vec<String> {
+        let validator = jsonschema::validator_for(schema).expect("schema compiles");
+        validator
+            .iter_errors(instance)
+            .map(|e| format!("{}: {e}", e.instance_path))
+            .collect()
+    }
+
+    fn example_policy() -> Value {
+        let text = include_str!("../sentinel.policy.toml.example");
+        let policy: toml::Value = toml::from_str(text).expect("example policy parses");
+        serde_json::to_value(policy).unwrap()
+    }
+
+    #[test]
+    fn status_report_matches_status_schema() {
+        let report = StatusReport {
+            schema: REPORT_SCHEMA,
+            overall: Overall::Error,
+            backend: "nftables".into(),
+            active_profile: "default".into(),
+            last_check: chrono::Utc::now(),
+            summary: Summary {
+                checks_total: 3,
+                checks_passed: 1,
+                checks_warn: 0,
+                checks_failed: 1,
+                checks_skipped: 1,
+            },
+            findings: vec![
+                Finding::pass("ruleset-drift", "ruleset matches the baseline"),
+                Finding::new("missing-fragment", Overall::Error, "fragment not found")
+                    .with_evidence(Evidence::rule("tcp dport 22 accept").in_zone("public"))
+                    .with_values("tcp dport 22 accept", "none")
+                    .with_remediation("nft add rule inet filter input tcp dport 22 accept"),
+                Finding::skipped("missing-service", "services are not checked by nftables"),
+            ],
+        };
+        let value = serde_json::to_value(&report).unwrap();
+        assert_eq!(errors(&status(), &value), Vec::<String>::new());
+    }
+
+    #[test]
+    fn example_policy_matches_policy_schema() {
+        let example = example_policy();
+        let schema = policy(v2::LATEST).unwrap();
+        assert_eq!(errors(&schema, &example), Vec::<String>::new());
+    }
+
+    #[test]
+    fn policy_schema_rejects_the_other_layout() {
+        let example = example_policy();
+        assert!(!errors(&policy(1).unwrap(), &example).is_empty());
+
+        let mut strings = example;
+        strings["checks"]["required_services"] = json!(["ssh"]);
+        assert!(!errors(&policy(2).unwrap(), &strings).is_empty());
+    }
+}
@@ -1,7 +1,12 @@
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Status report schema written by this version. Schema 2 added `evidence`,
+/// `expected`, `actual` and `remediation` to findings.
+pub const REPORT_SCHEMA: u32 = 2;
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct StatusReport {
     pub schema: u32,
     pub overall: Overall,
@@ -12,7 +17,7 @@ pub struct StatusReport {
     pub findings: Vec<Finding>,
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum Overall {
     Ok,
@@ -30,18 +35,170 @@ impl Overall {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Counts of the check results in a report; `checks_total` covers all of them,
+/// skipped ones included.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Summary {
     pub checks_total: u32,
+    #[serde(default)]
+    pub checks_passed: u32,
     pub checks_warn: u32,
     pub checks_failed: u32,
+    #[serde(default)]
+    pub checks_skipped: u32,
+}
+
+/// Outcome of one check.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    /// Not evaluated: unsupported by the backend or a prerequisite failed.
+    Skipped,
+}
+
+impl CheckStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "pass",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Fail => "fail",
+            CheckStatus::Skipped => "skipped",
+        }
+    }
+}
+
+impl From<Overall> for CheckStatus {
+    fn from(o: Overall) -> Self {
+        match o {
+            Overall::Ok => CheckStatus::Pass,
+            Overall::Warn => CheckStatus::Warn,
+            Overall::Error => CheckStatus::Fail,
+        }
+    }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Result of one check. Passed and skipped checks have severity `ok`; the id
+/// is the one the check fails with, except that a backend that is running is
+/// reported as e.g. `firewalld-running` rather than `firewalld-not-running`.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Finding {
     pub id: String,
+    pub status: CheckStatus,
     pub severity: Overall,
+    /// What failed, or what was verified / why the check was skipped.
     pub msg: String,
+    /// Backend that reported the finding.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    /// Earliest check in the retained history that reported this finding.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<DateTime<Utc>>,
+    /// Start of the current uninterrupted run of checks reporting it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<DateTime<Utc>>,
+    /// What the check looked at in the live state.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evidence: Option<Evidence>,
+    /// State the policy asks for, e.g. `policy drop`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+    /// State found on the host, e.g. `policy accept`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual: Option<String>,
+    /// Command (or step) that makes a failed check pass.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+}
+
+/// Where a check found (or missed) what the policy asks for.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct Evidence {
+    /// Policy rule that was matched or is missing, e.g. `22/tcp`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    /// firewalld zone(s) the check looked at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<String>,
+    /// Matching part of the live ruleset or zone listing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+impl Evidence {
+    pub fn rule(rule: impl Into<String>) -> Self {
+        Self {
+            rule: Some(rule.into()),
+            ..Self::default()
+        }
+    }
+
+    pub fn in_zone(mut self, zone: impl Into<String>) -> Self {
+        self.zone = Some(zone.into());
+        self
+    }
+
+    pub fn with_snippet(mut self, snippet: impl Into<String>) -> Self {
+        self.snippet = Some(snippet.into());
+        self
+    }
+}
+
+impl Finding {
+    pub fn new(id: impl Into<String>, severity: Overall, msg: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            status: severity.into(),
+            severity,
+            msg: msg.into(),
+            backend: None,
+            first_seen: None,
+            since: None,
+            evidence: None,
+            expected: None,
+            actual: None,
+            remediation: None,
+        }
+    }
+
+    /// A check that passed, usually under the id it is reported with when it fails.
+    pub fn pass(id: impl Into<String>, msg: impl Into<String>) -> Self {
+        Self::new(id, Overall::Ok, msg)
+    }
+
+    /// A check that was not evaluated; `msg` says why.
+    pub fn skipped(id: impl Into<String>, msg: impl Into<String>) -> Self {
+        Self {
+            status: CheckStatus::Skipped,
+            ..Self::new(id, Overall::Ok, msg)
+        }
+    }
+
+    pub fn with_evidence(mut self, evidence: Evidence) -> Self {
+        self.evidence = Some(evidence);
+        self
+    }
+
+    pub fn with_values(mut self, expected: impl Into<String>, actual: impl Into<String>) -> Self {
+        self.expected = Some(expected.into());
+        self.actual = Some(actual.into());
+        self
+    }
+
+    pub fn with_remediation(mut self, remediation: impl Into<String>) -> Self {
+        self.remediation = Some(remediation.into());
+        self
+    }
+
+    /// Change the severity of an evaluated check (`[checks.severity]`).
+    pub fn set_severity(&mut self, severity: Overall) {
+        if self.status != CheckStatus::Skipped {
+            self.severity = severity;
+            self.status = severity.into();
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -70,3 +227,67 @@ impl From<Overall> for ExitStatus {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn findings_carry_evidence_and_remediation() {
+        let finding = Finding::new(
+            "nft-chain-policy:inet filter/input",
+            Overall::Error,
+            "input policy is accept",
+        )
+        .with_evidence(
+            Evidence::rule("inet filter/input policy drop")
+                .with_snippet("type filter hook input priority 0; policy accept;"),
+        )
+        .with_values("policy drop", "policy accept")
+        .with_remediation("nft chain inet filter input '{ policy drop; }'");
+        assert_eq!(
+            serde_json::to_value(&finding).unwrap(),
+            json!({
+                "id": "nft-chain-policy:inet filter/input",
+                "status": "fail",
+                "severity": "error",
+                "msg": "input policy is accept",
+                "evidence": {
+                    "rule": "inet filter/input policy drop",
+                    "snippet": "type filter hook input priority 0; policy accept;",
+                },
+                "expected": "policy drop",
+                "actual": "policy accept",
+                "remediation": "nft chain inet filter input '{ policy drop; }'",
+            })
+        );
+        // unset fields are left out rather than written as null
+        assert_eq!(
+            serde_json::to_value(Finding::pass("ufw-active", "ufw is active")).unwrap(),
+            json!({ "id": "ufw-active", "status": "pass", "severity": "ok", "msg": "ufw is active" })
+        );
+    }
+
+    #[test]
+    fn reads_reports_without_the_new_fields() {
+        let report: StatusReport = serde_json::from_value(json!({
+            "schema": 1,
+            "overall": "warn",
+            "backend": "firewalld",
+            "active_profile": "default",
+            "last_check": "2026-10-01T12:00:00Z",
+            "summary": { "checks_total": 1, "checks_warn": 1, "checks_failed": 0 },
+            "findings": [
+                { "id": "missing-service:ssh", "status": "warn", "severity": "warn", "msg": "ssh is not enabled" },
+            ],
+        }))
+        .unwrap();
+        assert_eq!(report.summary.checks_passed, 0);
+        assert_eq!(report.summary.checks_skipped, 0);
+        let finding = &report.findings[0];
+        assert!(finding.evidence.is_none());
+        assert!(finding.expected.is_none() && finding.actual.is_none());
+        assert!(finding.remediation.is_none());
+    }
+}
@@ -0,0 +1,126 @@
+use anyhow::{Context, Result};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+/// Replace `path` atomically: write a temp file in the same directory, fsync
+/// it, rename it over the target and fsync the directory. Readers such as
+/// Waybar never observe a partially written file.
+pub fn write_atomic(path: &Path, contents: &[u8], mode: u32) -> Result<()> {
+    let dir = match path.parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("failed to create directory: {}", dir.display()))?;
+
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("not a file path: {}", path.display()))?
+        .to_string_lossy();
+    let (tmp, mut f) = create_temp(dir, &file_name, mode)?;
+
+    let res = (|| -> Result<()> {
+        // mode passed to open() is filtered by the umask; set it explicitly
+        f.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(mode))
+            .with_context(|| format!("failed to chmod temp file: {}", tmp.display()))?;
+        f.write_all(contents)
+            .with_context(|| format!("failed to write temp file: {}", tmp.display()))?;
+        f.sync_all()
+            .with_context(|| format!("failed to fsync temp file: {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("failed to rename {} -> {}", tmp.display(), path.display()))?;
+        Ok(())
+    })();
+
+    if res.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+        return res;
+    }
+
+    if let Ok(d) = std::fs::File::open(dir) {
+        let _ = d.sync_all();
+    }
+    Ok(())
+}
+
+/// Create `.<name>.<random>.tmp` in `dir` with `O_EXCL`, so a file or symlink
+/// planted under a guessed name is never opened; retried on collisions.
+fn create_temp(dir: &Path, file_name: &str, mode: u32) -> Result<(PathBuf, std::fs::File)> {
+    let state = std::collections::hash_map::RandomState::new();
+    for attempt in 0u32..100 {
+        let mut hasher = state.build_hasher();
+        hasher.write_u32(std::process::id());
+        hasher.write_u32(attempt);
+        let tmp = dir.join(format!(".{file_name}.{:016x}.tmp", hasher.finish()));
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(mode)
+            .open(&tmp)
+        {
+            Ok(f) => return Ok((tmp, f)),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to create temp file: {}", tmp.display()))
+            }
+        }
+    }
+    anyhow::bail!("failed to create a temp file in {}", dir.display())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("sentinel-statusfile-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn mode(path: &Path) -> u32 {
+        std::fs::metadata(path).unwrap().permissions().mode() & 0o7777
+    }
+
+    #[test]
+    fn writes_with_the_requested_mode() {
+        let dir = scratch("modes");
+        let path = dir.join("status.json");
+
+        write_atomic(&path, b"{}", 0o600).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"{}");
+        assert_eq!(mode(&path), 0o600);
+
+        // not filtered by the umask, and replaced rather than rewritten
+        write_atomic(&path, b"{\"overall\":\"ok\"}", 0o664).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"{\"overall\":\"ok\"}");
+        assert_eq!(mode(&path), 0o664);
+
+        let entries: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(entries.len(), 1, "temp files left behind");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn replaces_a_symlink_instead_of_writing_through_it() {
+        let dir = scratch("symlink");
+        std::fs::create_dir_all(&dir).unwrap();
+        let victim = dir.join("victim");
+        std::fs::write(&victim, "keep").unwrap();
+        let path = dir.join("status.json");
+        std::os::unix::fs::symlink(&victim, &path).unwrap();
+
+        write_atomic(&path, b"{}", 0o644).unwrap();
+        assert_eq!(std::fs::read_to_string(&victim).unwrap(), "keep");
+        assert!(!std::fs::symlink_metadata(&path)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
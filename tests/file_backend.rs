@@ -0,0 +1,162 @@
+//! Recorded command outputs under `tests/snapshots/<name>/` replayed through
+//! `FileBackend` and checked against the `policy.toml` next to them.
+
+use std::path::PathBuf;
+
+use sentinelctl::{
+    backends::{builtin_backends, file, file::FileBackend, Backend},
+    config::load_policy,
+    status::{CheckStatus, Finding},
+};
+
+use CheckStatus::{Fail, Pass, Skipped, Warn};
+
+async fn replay(name: &str) -> Vec<Finding> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(name);
+    let recorded = file::recorded_backend(&dir).unwrap();
+    let inner = builtin_backends()
+        .into_iter()
+        .find(|b| b.name() == recorded)
+        .unwrap();
+    let backend = FileBackend::load(inner, &dir).unwrap();
+    let policy = load_policy(&dir.join("policy.toml")).unwrap();
+    let snap = backend.snapshot().await.unwrap();
+    backend.validate(&policy, &snap).await.unwrap()
+}
+
+fn results(findings: &[Finding]) -> Vec<(&str, CheckStatus)> {
+    findings.iter().map(|f| (f.id.as_str(), f.status)).collect()
+}
+
+fn find<'a>(findings: &'a [Finding], id: &str) -> &'a Finding {
+    findings.iter().find(|f| f.id == id).unwrap()
+}
+
+#[tokio::test]
+async fn firewalld() {
+    let findings = replay("firewalld").await;
+    assert_eq!(
+        results(&findings),
+        [
+            ("firewalld-running", Pass),
+            ("missing-service:dhcpv6-client", Pass),
+            ("missing-service:ssh", Warn),
+            ("missing-port:8080/tcp", Pass),
+            ("missing-port:3000/tcp", Pass),
+            ("missing-port:3000/udp", Warn),
+            ("forbidden-service:telnet", Fail),
+            ("forbidden-port:3389/tcp", Fail),
+            ("forbidden-port:22/tcp", Pass),
+            ("missing-service:work/ssh", Pass),
+            ("unbound-interface:wg1", Warn),
+            ("forbidden-service:public/telnet", Fail),
+            ("missing-fragment:tcp dport 22 accept", Skipped),
+        ]
+    );
+    // wg1 is in no zone, so its checks ran against the default zone
+    let unbound = find(&findings, "unbound-interface:wg1");
+    assert_eq!(
+        unbound.evidence.as_ref().unwrap().zone.as_deref(),
+        Some("public")
+    );
+    // both required and forbidden ports match the listed 3000-3400/tcp range
+    assert!(find(&findings, "missing-port:3000/tcp")
+        .msg
+        .ends_with("3000/tcp (via 3000-3400/tcp)"));
+    let rdp = find(&findings, "forbidden-port:3389/tcp");
+    assert_eq!(
+        rdp.evidence.as_ref().unwrap().zone.as_deref(),
+        Some("public")
+    );
+}
+
+#[tokio::test]
+async fn firewalld_not_running() {
+    let findings = replay("firewalld-stopped").await;
+    assert_eq!(findings[0].id, "firewalld-not-running");
+    assert_eq!(findings[0].status, Fail);
+    assert!(findings[1..].iter().all(|f| f.status == Skipped));
+}
+
+#[tokio::test]
+async fn ufw() {
+    let findings = replay("ufw").await;
+    assert_eq!(
+        results(&findings),
+        [
+            ("ufw-active", Pass),
+            ("ufw-default:incoming", Pass),
+            ("ufw-default:routed", Warn),
+            ("missing-service:Nginx Full", Pass),
+            ("forbidden-service:Samba", Pass),
+            ("missing-port:22/tcp", Pass),
+            ("missing-port:443/tcp", Warn),
+            ("forbidden-port:3306/tcp", Pass),
+            ("forbidden-port:5432/tcp", Fail),
+        ]
+    );
+    assert_eq!(
+        find(&findings, "forbidden-port:5432/tcp")
+            .remediation
+            .as_deref(),
+        Some("ufw delete limit 5432/tcp")
+    );
+}
+
+#[tokio::test]
+async fn nftables() {
+    let findings = replay("nftables").await;
+    assert_eq!(
+        results(&findings),
+        [
+            ("nftables-available", Pass),
+            ("missing-fragment:tcp dport { 22, 80 } accept", Pass),
+            ("missing-fragment:tcp dport 443 accept", Warn),
+            ("forbidden-fragment:ct state invalid drop", Fail),
+            ("forbidden-port:80/tcp", Fail),
+            ("forbidden-port:23/tcp", Pass),
+            ("nft-table:inet filter", Pass),
+            ("nft-table:ip nat", Warn),
+            ("nft-chain:inet filter/hook:input", Pass),
+            ("nft-chain-hook:inet filter/hook:input", Pass),
+            ("nft-chain-policy:inet filter/hook:input", Pass),
+            ("nft-rule:inet filter/input/tcp dport 450 accept", Pass),
+            (
+                "nft-rule-order:inet filter/input/tcp dport 450 accept",
+                Warn
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn iptables() {
+    let findings = replay("iptables").await;
+    assert_eq!(
+        results(&findings),
+        [
+            ("iptables-available", Pass),
+            ("forbidden-fragment:--ctstate INVALID -j DROP", Fail),
+            ("missing-port:443/tcp", Pass),
+            ("forbidden-port:23/tcp", Fail),
+            ("ipt-chain:ipv4/filter/INPUT", Pass),
+            ("ipt-chain-policy:ipv4/filter/INPUT", Warn),
+            ("ipt-chain:ipv6/filter/INPUT", Pass),
+            ("ipt-chain-policy:ipv6/filter/INPUT", Pass),
+            ("ipt-rule:ipv4/filter/INPUT/tcp dport 443 ACCEPT", Pass),
+            (
+                "ipt-rule-order:ipv4/filter/INPUT/tcp dport 443 ACCEPT",
+                Warn
+            ),
+        ]
+    );
+    // 23/tcp is open in both families; the ipv4 rule is reported first
+    let telnet = find(&findings, "forbidden-port:23/tcp");
+    assert!(telnet
+        .evidence
+        .as_ref()
+        .and_then(|e| e.snippet.as_deref())
+        .is_some_and(|s| s.contains("--dport 23 -j ACCEPT")));
+}